@@ -0,0 +1,137 @@
+//! Helpers shared by the decoder benchmarks.
+
+// Every bench compiles its own copy, not all of them use everything
+#![allow(dead_code)]
+
+use fountaincode::{
+    decoder::{CatchResult, Decoder},
+    droplet::{DropType, Droplet},
+    encoder::{Encoder, EncoderType},
+    header::EdgeGenerator,
+    xor::xor_bytes,
+};
+use rand::{thread_rng, Rng};
+
+// Droplets of a random encoder, just enough for a peeling decoder.
+pub fn peeling_droplets(size: usize, chunk_len: usize) -> Vec<Droplet> {
+    let msg: Vec<u8> = (0..size).map(|_| thread_rng().gen()).collect();
+    let mut enc = Encoder::robust(msg, chunk_len, EncoderType::Random, 0.1, None, 0.05);
+    let mut dec = Decoder::new(size, chunk_len);
+    let mut drops = Vec::new();
+    loop {
+        let drop = enc.drop();
+        drops.push(drop.clone());
+        if let CatchResult::Finished(..) = dec.catch(drop) {
+            return drops;
+        }
+    }
+}
+
+struct Pending {
+    edges_idx: Vec<usize>,
+    data: Vec<u8>,
+}
+
+/// The peeling decoder as it was before droplets moved into an arena,
+/// kept as the baseline of the Peeling benchmarks. Every block buffers
+/// its own clone of each droplet waiting for it, payload included.
+pub struct CloningDecoder {
+    len: usize,
+    blocksize: usize,
+    data: Vec<u8>,
+    is_known: Vec<bool>,
+    edges: Vec<Vec<Pending>>,
+    unknown_chunks: usize,
+    generator: EdgeGenerator,
+}
+
+impl CloningDecoder {
+    pub fn new(len: usize, blocksize: usize) -> Self {
+        let cnt_blocks = len.div_ceil(blocksize);
+        CloningDecoder {
+            len,
+            blocksize,
+            data: vec![0; cnt_blocks * blocksize],
+            is_known: vec![false; cnt_blocks],
+            edges: (0..cnt_blocks).map(|_| Vec::new()).collect(),
+            unknown_chunks: cnt_blocks,
+            generator: EdgeGenerator::default(),
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.unknown_chunks == 0
+    }
+
+    pub fn into_data(mut self) -> Vec<u8> {
+        self.data.truncate(self.len);
+        self.data
+    }
+
+    pub fn catch(&mut self, drop: Droplet) {
+        let mut edges_idx = match drop.droptype {
+            DropType::Seeded(seed, degree) => {
+                self.generator.sample(seed, self.is_known.len(), degree)
+            }
+            DropType::Edges(idx) => vec![idx],
+        };
+        // Blocks drawn twice cancel out
+        edges_idx.sort_unstable();
+        let mut distinct: Vec<usize> = Vec::with_capacity(edges_idx.len());
+        for idx in edges_idx {
+            if distinct.last() == Some(&idx) {
+                distinct.pop();
+            } else {
+                distinct.push(idx);
+            }
+        }
+        self.process(Pending {
+            edges_idx: distinct,
+            data: drop.data,
+        });
+    }
+
+    fn process(&mut self, drop: Pending) {
+        // Droplets taken from a block's buffer are still buffered at
+        // their other unknown blocks
+        let mut drops = vec![(drop, false)];
+        while let Some((mut drop, is_buffered)) = drops.pop() {
+            self.reduce(&mut drop);
+            match drop.edges_idx.len() {
+                0 => {}
+                1 => {
+                    let idx = drop.edges_idx[0];
+                    if self.is_known[idx] {
+                        continue;
+                    }
+                    let begin = idx * self.blocksize;
+                    self.data[begin..begin + self.blocksize].copy_from_slice(&drop.data);
+                    self.is_known[idx] = true;
+                    self.unknown_chunks -= 1;
+                    drops.extend(self.edges[idx].drain(..).map(|edge| (edge, true)));
+                }
+                _ if is_buffered => {}
+                _ => {
+                    for &ed in &drop.edges_idx {
+                        self.edges[ed].push(Pending {
+                            edges_idx: drop.edges_idx.clone(),
+                            data: drop.data.clone(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    fn reduce(&self, drop: &mut Pending) {
+        let Pending { edges_idx, data } = drop;
+        edges_idx.retain(|&ed| {
+            let is_known = self.is_known[ed];
+            if is_known {
+                let begin = ed * self.blocksize;
+                xor_bytes(data, &self.data[begin..begin + self.blocksize]);
+            }
+            !is_known
+        });
+    }
+}
@@ -1,6 +1,10 @@
-use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+mod common;
+
+use common::{peeling_droplets, CloningDecoder};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
 use fountaincode::{
     decoder::{CatchResult, Decoder},
+    droplet::Droplet,
     encoder::{Encoder, EncoderType},
 };
 use itertools::iproduct;
@@ -101,5 +105,55 @@ fn bench_ideal_vs_robust(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, bench_ideal_vs_robust);
+fn peeling_decode(size: usize, chunk_len: usize, drops: Vec<Droplet>) {
+    let mut dec = Decoder::new(size, chunk_len);
+    for drop in drops {
+        dec.catch(drop);
+    }
+    assert!(dec.is_finished());
+}
+
+fn cloning_decode(size: usize, chunk_len: usize, drops: Vec<Droplet>) {
+    let mut dec = CloningDecoder::new(size, chunk_len);
+    for drop in drops {
+        dec.catch(drop);
+    }
+    assert!(dec.is_finished());
+}
+
+fn bench_peeling(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Peeling");
+    group.sample_size(20);
+
+    for &(size, chunk_len) in &[(1_000_000, 1_000), (4_000_000, 1_000)] {
+        let drops = peeling_droplets(size, chunk_len);
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!("{}_{}", size, chunk_len)),
+            &drops,
+            |b, drops| {
+                b.iter_batched(
+                    || drops.clone(),
+                    |drops| peeling_decode(size, chunk_len, drops),
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+        // The decoder before droplets moved into an arena, as baseline
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!("cloning_{}_{}", size, chunk_len)),
+            &drops,
+            |b, drops| {
+                b.iter_batched(
+                    || drops.clone(),
+                    |drops| cloning_decode(size, chunk_len, drops),
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_ideal_vs_robust, bench_peeling);
 criterion_main!(benches);
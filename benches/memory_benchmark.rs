@@ -0,0 +1,82 @@
+//! Peak heap usage of the peeling decoder, next to the decoder that
+//! cloned every droplet into each of its blocks as baseline.
+//!
+//! Counting allocations needs a global allocator, which would also slow
+//! down every other benchmark in the same binary, so the measurement
+//! lives in its own bench target:
+//!
+//! ```text
+//! cargo bench --bench memory_benchmark
+//! ```
+mod common;
+
+use common::{peeling_droplets, CloningDecoder};
+use fountaincode::decoder::Decoder;
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+// Tracks the peak heap usage, to compare the memory the decoders need.
+struct PeakAlloc;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for PeakAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let now = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK.fetch_max(now, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: PeakAlloc = PeakAlloc;
+
+// Runs `decode` and returns its result with the peak heap it used on
+// top of what was allocated before.
+fn peak_kib<T>(decode: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    PEAK.store(before, Ordering::Relaxed);
+    let result = decode();
+    (result, (PEAK.load(Ordering::Relaxed) - before) / 1024)
+}
+
+fn main() {
+    for &(size, chunk_len) in &[(1_000_000, 1_000), (4_000_000, 1_000)] {
+        let drops = peeling_droplets(size, chunk_len);
+
+        // The peak includes the droplets handed to the decoder
+        let (data, arena) = peak_kib(|| {
+            let mut dec = Decoder::new(size, chunk_len);
+            for drop in drops.clone() {
+                dec.catch(drop);
+            }
+            assert!(dec.is_finished());
+            dec.into_data()
+        });
+        let (baseline, cloning) = peak_kib(|| {
+            let mut dec = CloningDecoder::new(size, chunk_len);
+            for drop in drops.clone() {
+                dec.catch(drop);
+            }
+            assert!(dec.is_finished());
+            dec.into_data()
+        });
+        assert_eq!(baseline, data);
+        println!(
+            "Peeling/{}_{}: {} droplets, peak decoder heap {} KiB, cloning baseline {} KiB",
+            size,
+            chunk_len,
+            drops.len(),
+            arena,
+            cloning
+        );
+    }
+}
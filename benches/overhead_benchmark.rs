@@ -0,0 +1,57 @@
+//! Droplets a peeling decoder needs per block, for Robust Soliton
+//! parameters around the defaults of the builder module:
+//!
+//! ```text
+//! cargo bench --bench overhead_benchmark
+//! ```
+//!
+//! Every line lists the average of `droplets / k` over a number of
+//! seeds for each `c` and `delta`, the best one first.
+use fountaincode::{
+    builder::{default_c, DEFAULT_DELTA},
+    decoder::{CatchResult, Decoder},
+    encoder::{Encoder, EncoderType},
+};
+
+fn droplets_needed(k: usize, c: f32, delta: f32, seed: u64) -> usize {
+    let blocksize = 8;
+    let msg: Vec<u8> = (0..k * blocksize).map(|i| (i * 7) as u8).collect();
+    let enc = Encoder::robust(msg, blocksize, EncoderType::Random, c, None, delta).with_seed(seed);
+    let mut dec = Decoder::new(k * blocksize, blocksize);
+    (0..)
+        .find(|&n| matches!(dec.catch(enc.drop_at(n)), CatchResult::Finished(..)))
+        .unwrap()
+        + 1
+}
+
+fn overhead(k: usize, c: f32, delta: f32, runs: u64) -> f64 {
+    let total: usize = (0..runs)
+        .map(|seed| droplets_needed(k, c, delta, seed))
+        .sum();
+    total as f64 / runs as f64 / k as f64
+}
+
+fn main() {
+    for &k in &[10, 30, 100, 300, 1_000, 3_000, 10_000] {
+        let runs = if k >= 3_000 { 10 } else { 60 };
+        let mut results = Vec::new();
+        for &c in &[0.01, 0.03, 0.05, 0.1, 0.2, 0.3] {
+            for &delta in &[0.05, 0.2, 0.5] {
+                results.push((overhead(k, c, delta, runs), c, delta));
+            }
+        }
+        results.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        let line: Vec<String> = results
+            .iter()
+            .map(|(overhead, c, delta)| format!("{}/{}: {:.3}", c, delta, overhead))
+            .collect();
+        println!(
+            "k = {}, default {}/{}: {:.3}, {}",
+            k,
+            default_c(k),
+            DEFAULT_DELTA,
+            overhead(k, default_c(k), DEFAULT_DELTA, runs),
+            line.join(", ")
+        );
+    }
+}
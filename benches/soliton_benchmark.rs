@@ -0,0 +1,66 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use fountaincode::soliton::Soliton;
+use rand::{distributions::Distribution, rngs::StdRng, Rng, SeedableRng};
+
+// The former sampling of the Robust Soliton distribution, which walks
+// the probabilities from degree 1 on for every draw.
+fn linear_sample<R: Rng>(sol: &Soliton, rng: &mut R) -> usize {
+    let (k, r, delta, beta, m) = match *sol {
+        Soliton::Robust {
+            k,
+            r,
+            delta,
+            beta,
+            m,
+            ..
+        } => (k, r, delta, beta, m),
+        Soliton::Ideal { .. } => unreachable!(),
+    };
+    let rho = |i: usize| {
+        if i == 1 {
+            1.0 / k as f32
+        } else {
+            1.0 / (i * (i - 1)) as f32
+        }
+    };
+    let tau = |i: usize| {
+        if i >= 1 && i < m {
+            (1 / (i * m)) as f32
+        } else if i == m {
+            (r / delta).ln() / m as f32
+        } else {
+            0.0
+        }
+    };
+
+    let mut sum = 0.0;
+    let mut index = 1;
+    let u = rng.gen::<f32>();
+    while sum <= u {
+        sum += (rho(index) + tau(index)) / beta;
+        index += 1;
+    }
+    index - 1
+}
+
+fn bench_robust_sampling(c: &mut Criterion) {
+    let mut group = c.benchmark_group("RobustSampling");
+
+    for &k in &[100_usize, 1_000, 10_000, 100_000] {
+        let sol = Soliton::robust(k, 0.2, None, 0.05);
+
+        group.bench_with_input(BenchmarkId::new("Linear", k), &sol, |b, sol| {
+            let mut rng = StdRng::seed_from_u64(0);
+            b.iter(|| linear_sample(sol, &mut rng))
+        });
+
+        group.bench_with_input(BenchmarkId::new("Cdf", k), &sol, |b, sol| {
+            let mut rng = StdRng::seed_from_u64(0);
+            b.iter(|| sol.sample(&mut rng))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_robust_sampling);
+criterion_main!(benches);
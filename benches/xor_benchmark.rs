@@ -0,0 +1,67 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use fountaincode::xor::{xor_bytes, xor_many, Kernel};
+use rand::{thread_rng, Rng};
+
+// The former implementation of `xor_bytes`.
+#[inline(never)]
+fn xor_scalar(lhs: &mut [u8], rhs: &[u8]) {
+    for (l, r) in lhs.iter_mut().zip(rhs) {
+        *l ^= *r;
+    }
+}
+
+fn bench_kernels(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Xor");
+    let kernels = [Kernel::Avx2, Kernel::Sse2, Kernel::Neon, Kernel::Words];
+
+    for &size in &[64, 1_024, 65_536] {
+        let mut lhs: Vec<u8> = (0..size).map(|_| thread_rng().gen()).collect();
+        let rhs: Vec<u8> = (0..size).map(|_| thread_rng().gen()).collect();
+        group.throughput(Throughput::Bytes(size as u64));
+
+        group.bench_with_input(BenchmarkId::new("Scalar", size), &rhs, |b, rhs| {
+            b.iter(|| xor_scalar(&mut lhs, rhs))
+        });
+        group.bench_with_input(BenchmarkId::new("Detected", size), &rhs, |b, rhs| {
+            b.iter(|| xor_bytes(&mut lhs, rhs))
+        });
+        for kernel in kernels.iter().filter(|k| k.is_supported()) {
+            group.bench_with_input(
+                BenchmarkId::new(format!("{:?}", kernel), size),
+                &rhs,
+                |b, rhs| b.iter(|| kernel.xor(&mut lhs, rhs)),
+            );
+        }
+    }
+    group.finish();
+}
+
+// XORs `degree` blocks into a droplet, like the encoder does.
+fn bench_many(c: &mut Criterion) {
+    let mut group = c.benchmark_group("XorMany");
+    let blocksize = 1_024;
+    let blocks: Vec<Vec<u8>> = (0..64)
+        .map(|_| (0..blocksize).map(|_| thread_rng().gen()).collect())
+        .collect();
+    let mut dst = vec![0; blocksize];
+
+    for &degree in &[2, 8, 64] {
+        let srcs: Vec<&[u8]> = blocks[..degree].iter().map(|b| &b[..]).collect();
+        group.throughput(Throughput::Bytes((degree * blocksize) as u64));
+
+        group.bench_with_input(BenchmarkId::new("OneByOne", degree), &srcs, |b, srcs| {
+            b.iter(|| {
+                for src in srcs {
+                    xor_bytes(&mut dst, src);
+                }
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("SinglePass", degree), &srcs, |b, srcs| {
+            b.iter(|| xor_many(&mut dst, srcs))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_kernels, bench_many);
+criterion_main!(benches);
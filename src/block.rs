@@ -1,15 +1,14 @@
-use crate::droplet::RxDroplet;
-
 #[derive(Clone)]
 pub struct Block {
     pub idx: usize,
-    pub edges: Vec<RxDroplet>,
+    /// Indices of the droplets waiting for this block
+    pub edges: Vec<usize>,
     pub begin_at: usize,
     pub is_known: bool,
 }
 
 impl Block {
-    pub fn new(idx: usize, edges: Vec<RxDroplet>, begin_at: usize, is_known: bool) -> Block {
+    pub fn new(idx: usize, edges: Vec<usize>, begin_at: usize, is_known: bool) -> Block {
         Block {
             idx,
             edges,
@@ -0,0 +1,369 @@
+//! Builders that check the configuration before creating an
+//! [`Encoder`] or a [`Decoder`].
+//!
+//! The constructors of [`Encoder`] take the distribution parameters
+//! positionally and panic on a block size of zero, and drawing
+//! droplets of empty input panics. The builders take named settings, fill in the rest with
+//! defaults and report invalid settings as a [`ConfigError`].
+//!
+//! # Example
+//!
+//! ```
+//! use fountaincode::{
+//!     builder::{DecoderBuilder, EncoderBuilder},
+//!     decoder::CatchResult,
+//! };
+//!
+//! let msg: Vec<u8> = (0..10_000).map(|i| i as u8).collect();
+//! let mut enc = EncoderBuilder::new(msg.clone())
+//!     .blocksize(256)
+//!     .delta(0.01)
+//!     .build()
+//!     .unwrap();
+//! let mut dec = DecoderBuilder::new(msg.len()).blocksize(256).build().unwrap();
+//!
+//! loop {
+//!     if let CatchResult::Finished(data, _) = dec.catch(enc.drop()) {
+//!         assert_eq!(data, &msg[..]);
+//!         break;
+//!     }
+//! }
+//! ```
+use crate::{
+    decoder::{Decoder, DecoderType},
+    encoder::{Encoder, EncoderType},
+    header::EdgeGenerator,
+    soliton::{Soliton, SolitonError},
+};
+use std::{error, fmt};
+
+/// Block size used unless one is set, small enough for a UDP datagram.
+pub const DEFAULT_BLOCKSIZE: usize = 1024;
+/// Robust Soliton `delta` used unless one is set.
+pub const DEFAULT_DELTA: f32 = 0.5;
+
+/// Robust Soliton `c` used for `k` blocks unless one is set.
+///
+/// Taken from a sweep over `c` in `0.01..=0.3` and `delta` in
+/// `0.05..=0.5` for `k` from 10 to 10000, picking the values a peeling
+/// decoder needs the fewest droplets with on average, see
+/// `benches/overhead_benchmark.rs`. `delta = 0.5` won throughout, the
+/// best `c` shrinks as `k` grows:
+///
+/// ```text
+/// k            c      droplets / k
+/// < 100        0.2    1.56 (k = 10)   1.44 (k = 30)
+/// 100..1000    0.05   1.31 (k = 100)  1.21 (k = 300)
+/// >= 1000      0.03   1.11 (k = 1000) 1.04 (k = 10000)
+/// ```
+pub fn default_c(k: usize) -> f32 {
+    if k < 100 {
+        0.2
+    } else if k < 1000 {
+        0.05
+    } else {
+        0.03
+    }
+}
+
+/// Builds an [`Encoder`] with a Soliton distribution.
+///
+/// By default the encoder sends random droplets of
+/// [`DEFAULT_BLOCKSIZE`] bytes drawn from the Robust Soliton
+/// distribution for the `k` blocks of the message, with
+/// [`default_c`]`(k)`, [`DEFAULT_DELTA`] and the spike at `k / R`.
+#[derive(Debug, Clone)]
+pub struct EncoderBuilder<S = Vec<u8>> {
+    data: S,
+    pub(crate) blocksize: usize,
+    encodertype: EncoderType,
+    systematic_rounds: usize,
+    repair_interval: Option<usize>,
+    ideal: bool,
+    c: Option<f32>,
+    delta: Option<f32>,
+    spike: Option<usize>,
+    pub(crate) seed: Option<u64>,
+    generator: EdgeGenerator,
+}
+
+impl<S> EncoderBuilder<S> {
+    /// Starts building an encoder for `data`.
+    pub fn new(data: S) -> Self {
+        EncoderBuilder {
+            data,
+            blocksize: DEFAULT_BLOCKSIZE,
+            encodertype: EncoderType::Random,
+            systematic_rounds: 2,
+            repair_interval: None,
+            ideal: false,
+            c: None,
+            delta: None,
+            spike: None,
+            seed: None,
+            generator: EdgeGenerator::default(),
+        }
+    }
+
+    /// Replaces the data, keeping all settings.
+    pub fn data<T>(self, data: T) -> EncoderBuilder<T> {
+        EncoderBuilder {
+            data,
+            blocksize: self.blocksize,
+            encodertype: self.encodertype,
+            systematic_rounds: self.systematic_rounds,
+            repair_interval: self.repair_interval,
+            ideal: self.ideal,
+            c: self.c,
+            delta: self.delta,
+            spike: self.spike,
+            seed: self.seed,
+            generator: self.generator,
+        }
+    }
+
+    /// Separates the data from the settings.
+    pub(crate) fn take_data(self) -> (S, EncoderBuilder<()>) {
+        let EncoderBuilder {
+            data,
+            blocksize,
+            encodertype,
+            systematic_rounds,
+            repair_interval,
+            ideal,
+            c,
+            delta,
+            spike,
+            seed,
+            generator,
+        } = self;
+        let settings = EncoderBuilder {
+            data: (),
+            blocksize,
+            encodertype,
+            systematic_rounds,
+            repair_interval,
+            ideal,
+            c,
+            delta,
+            spike,
+            seed,
+            generator,
+        };
+        (data, settings)
+    }
+
+    pub fn blocksize(mut self, blocksize: usize) -> Self {
+        self.blocksize = blocksize;
+        self
+    }
+
+    pub fn encoder_type(mut self, encodertype: EncoderType) -> Self {
+        self.encodertype = encodertype;
+        self
+    }
+
+    /// See [`Encoder::with_systematic_rounds`].
+    pub fn systematic_rounds(mut self, rounds: usize) -> Self {
+        self.systematic_rounds = rounds;
+        self
+    }
+
+    /// See [`Encoder::with_repair_interval`], must not be zero.
+    pub fn repair_interval(mut self, interval: usize) -> Self {
+        self.repair_interval = Some(interval);
+        self
+    }
+
+    /// Draws the degrees from the Ideal Soliton distribution instead.
+    /// `c`, `delta` and `spike` are ignored then.
+    pub fn ideal(mut self) -> Self {
+        self.ideal = true;
+        self
+    }
+
+    /// The Robust Soliton constant `c`, positive.
+    pub fn c(mut self, c: f32) -> Self {
+        self.c = Some(c);
+        self
+    }
+
+    /// The Robust Soliton failure probability, within `(0, 1)`.
+    pub fn delta(mut self, delta: f32) -> Self {
+        self.delta = Some(delta);
+        self
+    }
+
+    /// Places the Robust Soliton spike at `spike`, within `1..=k`.
+    pub fn spike(mut self, spike: usize) -> Self {
+        self.spike = Some(spike);
+        self
+    }
+
+    /// Derives the droplets from `seed`, see [`Encoder::with_seed`].
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn generator(mut self, generator: EdgeGenerator) -> Self {
+        self.generator = generator;
+        self
+    }
+
+    /// Checks every setting but the data and returns the distribution
+    /// for a message of `len` bytes.
+    pub(crate) fn distribution(&self, len: usize) -> Result<Soliton, ConfigError> {
+        check_blocksize(self.blocksize)?;
+        if self.repair_interval == Some(0) {
+            return Err(ConfigError::ZeroRepairInterval);
+        }
+        let k = len.div_ceil(self.blocksize);
+        if self.ideal {
+            Ok(Soliton::ideal(k))
+        } else {
+            let c = self.c.unwrap_or_else(|| default_c(k));
+            let delta = self.delta.unwrap_or(DEFAULT_DELTA);
+            Ok(Soliton::try_robust(k, c, self.spike, delta)?)
+        }
+    }
+}
+
+impl<S: AsRef<[u8]>> EncoderBuilder<S> {
+    /// Checks the configuration and creates the encoder.
+    pub fn build(self) -> Result<Encoder<Soliton, S>, ConfigError> {
+        let len = self.data.as_ref().len();
+        if len == 0 {
+            return Err(ConfigError::EmptyMessage);
+        }
+        let sol = self.distribution(len)?;
+        let mut enc = Encoder::with_distribution(self.data, self.blocksize, self.encodertype, sol)
+            .with_generator(self.generator)
+            .with_systematic_rounds(self.systematic_rounds);
+        if let Some(interval) = self.repair_interval {
+            enc = enc.with_repair_interval(interval);
+        }
+        if let Some(seed) = self.seed {
+            enc = enc.with_seed(seed);
+        }
+        Ok(enc)
+    }
+}
+
+/// Builds a [`Decoder`].
+///
+/// By default the decoder expects blocks of [`DEFAULT_BLOCKSIZE`] bytes
+/// and peels.
+#[derive(Debug, Clone)]
+pub struct DecoderBuilder {
+    pub(crate) len: usize,
+    pub(crate) blocksize: usize,
+    decodertype: DecoderType,
+    generator: EdgeGenerator,
+}
+
+impl DecoderBuilder {
+    /// Starts building a decoder for a message of `len` bytes.
+    pub fn new(len: usize) -> Self {
+        DecoderBuilder {
+            len,
+            blocksize: DEFAULT_BLOCKSIZE,
+            decodertype: DecoderType::Peeling,
+            generator: EdgeGenerator::default(),
+        }
+    }
+
+    pub fn blocksize(mut self, blocksize: usize) -> Self {
+        self.blocksize = blocksize;
+        self
+    }
+
+    /// Decodes a message of `len` bytes instead, keeping all settings.
+    pub(crate) fn with_len(mut self, len: usize) -> Self {
+        self.len = len;
+        self
+    }
+
+    pub fn decoder_type(mut self, decodertype: DecoderType) -> Self {
+        self.decodertype = decodertype;
+        self
+    }
+
+    pub fn generator(mut self, generator: EdgeGenerator) -> Self {
+        self.generator = generator;
+        self
+    }
+
+    /// Checks the configuration and creates the decoder.
+    pub fn build(self) -> Result<Decoder, ConfigError> {
+        if self.len == 0 {
+            return Err(ConfigError::EmptyMessage);
+        }
+        check_blocksize(self.blocksize)?;
+        Ok(
+            Decoder::with_type(self.len, self.blocksize, self.decodertype)
+                .with_generator(self.generator),
+        )
+    }
+}
+
+// Headers, droplets and snapshots carry the block size as a u32.
+pub(crate) fn check_blocksize(blocksize: usize) -> Result<(), ConfigError> {
+    if blocksize == 0 {
+        Err(ConfigError::ZeroBlocksize)
+    } else if blocksize as u64 > u32::MAX as u64 {
+        Err(ConfigError::BlocksizeTooLarge(blocksize))
+    } else {
+        Ok(())
+    }
+}
+
+/// Why a builder rejected its configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    /// The message is empty.
+    EmptyMessage,
+    /// The block size is zero.
+    ZeroBlocksize,
+    /// The block size does not fit into the u32 of the wire formats.
+    BlocksizeTooLarge(usize),
+    /// The repair interval is zero.
+    ZeroRepairInterval,
+    /// The maximum number of symbols per source block is zero.
+    ZeroBlockSymbols,
+    /// The object needs more source blocks than a droplet can address.
+    TooManySourceBlocks(usize),
+    /// The Robust Soliton parameters are invalid.
+    Soliton(SolitonError),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::EmptyMessage => write!(f, "the message is empty"),
+            ConfigError::ZeroBlocksize => write!(f, "block size is zero"),
+            ConfigError::BlocksizeTooLarge(blocksize) => {
+                write!(f, "block size of {} bytes is too large", blocksize)
+            }
+            ConfigError::ZeroRepairInterval => write!(f, "repair interval is zero"),
+            ConfigError::ZeroBlockSymbols => write!(f, "source blocks of zero symbols"),
+            ConfigError::TooManySourceBlocks(z) => write!(f, "too many source blocks: {}", z),
+            ConfigError::Soliton(err) => write!(f, "invalid Robust Soliton parameters: {}", err),
+        }
+    }
+}
+
+impl error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ConfigError::Soliton(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<SolitonError> for ConfigError {
+    fn from(err: SolitonError) -> Self {
+        ConfigError::Soliton(err)
+    }
+}
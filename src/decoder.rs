@@ -1,10 +1,19 @@
 use crate::{
     block::Block,
     droplet::{DropType, Droplet, RxDroplet},
-    encoder::get_sample_from_rng_by_seed,
+    gf2::{self, Equation},
+    header::{EdgeGenerator, SessionHeader},
     xor::xor_bytes,
 };
-use rand::distributions::Uniform;
+use std::{
+    cmp,
+    collections::{HashSet, VecDeque},
+    error, fmt, mem,
+};
+
+mod snapshot;
+
+pub use self::snapshot::{SnapshotError, DEFAULT_RESTORE_LIMIT, SNAPSHOT_VERSION};
 
 /// Decoder for Luby Transform codes.
 ///
@@ -36,150 +45,561 @@ use rand::distributions::Uniform;
 ///     }
 /// }
 /// ```
-pub struct Decoder {
-    total_length: usize,
+///
+/// The recovered message is written into a storage `S`, a `Vec<u8>` by
+/// default. [`Decoder::with_storage`] takes any other buffer, e.g. a
+/// memory-mapped output file from the [`mmap`](crate::mmap) module.
+pub struct Decoder<S = Vec<u8>> {
     blocksize: usize,
     unknown_chunks: usize,
     number_of_chunks: usize,
     cnt_received_drops: usize,
     blocks: Vec<Block>,
-    data: Vec<u8>,
-    dist: rand::distributions::Uniform<usize>,
+    store: Store<S>,
+    // Every droplet waiting for blocks is stored once, the blocks keep
+    // the indices of their droplets. Freed slots are reused.
+    droplets: Vec<RxDroplet>,
+    free: Vec<usize>,
+    // Known blocks whose droplets still have to be reduced
+    pending: Vec<usize>,
+    // Source blocks recovered by the last catch
+    recovered: Vec<usize>,
+    generator: EdgeGenerator,
+    decodertype: DecoderType,
+    cnt_eliminated: usize,
+    // Useful droplets needed before the next Gaussian elimination
+    next_elimination: usize,
+    // The droplets received so far, to spot duplicates
+    seen: Seen,
+    cnt_duplicates: usize,
+    cnt_redundant: usize,
 }
 
 #[derive(Debug)]
 pub struct Statistics {
+    /// All droplets received, including duplicate and redundant ones
     pub cnt_droplets: usize,
     pub cnt_chunks: usize,
+    /// Useful droplets per chunk in percent
     pub overhead: f32,
     pub unknown_chunks: usize,
+    /// Blocks recovered by Gaussian elimination rather than peeling
+    pub cnt_eliminated: usize,
+    /// Droplets received before, with the same seed or systematic index.
+    /// Only the latest 2k seeded droplets are remembered, a seeded
+    /// droplet repeated after that counts as a new one.
+    pub cnt_duplicates: usize,
+    /// Droplets that only covered known blocks
+    pub cnt_redundant: usize,
 }
 
 impl Decoder {
     pub fn new(len: usize, blocksize: usize) -> Decoder {
-        let number_of_chunks = (len + blocksize - 1) / blocksize;
-        let data: Vec<u8> = vec![0; number_of_chunks * blocksize];
-        let mut edges: Vec<Block> = Vec::with_capacity(number_of_chunks);
-        for i in 0..number_of_chunks {
+        Decoder::with_type(len, blocksize, DecoderType::Peeling)
+    }
+
+    pub fn with_type(len: usize, blocksize: usize, decodertype: DecoderType) -> Decoder {
+        Decoder::with_storage(vec![0; len], blocksize, decodertype)
+    }
+
+    /// Creates a decoder from the session header sent by the encoder.
+    pub fn from_header(header: &SessionHeader) -> Decoder {
+        Decoder::new(header.len(), header.blocksize()).with_generator(header.generator())
+    }
+
+    /// Returns the message without copying it. Unless decoding has
+    /// finished, the unknown blocks are zeroed.
+    pub fn into_data(self) -> Vec<u8> {
+        self.store.data
+    }
+}
+
+impl<S: AsRef<[u8]> + AsMut<[u8]>> Decoder<S> {
+    /// Creates a decoder that writes the recovered message straight
+    /// into `storage`. The message is exactly as long as `storage`.
+    pub fn with_storage(storage: S, blocksize: usize, decodertype: DecoderType) -> Decoder<S> {
+        Decoder::with_constraints(storage, blocksize, 0, Vec::new(), decodertype)
+    }
+
+    /// Creates a decoder whose droplets may also cover `cnt_aux`
+    /// auxiliary blocks, indexed after the source blocks. Every
+    /// constraint lists blocks that add up to zero and is fed to the
+    /// decoder like a droplet. Decoding finishes once all source blocks
+    /// are known.
+    pub(crate) fn with_constraints(
+        storage: S,
+        blocksize: usize,
+        cnt_aux: usize,
+        constraints: Vec<Vec<usize>>,
+        decodertype: DecoderType,
+    ) -> Decoder<S> {
+        let len = storage.as_ref().len();
+        let number_of_chunks = len.div_ceil(blocksize);
+        let cnt_blocks = number_of_chunks + cnt_aux;
+        let cnt_stored = len / blocksize;
+        let mut edges: Vec<Block> = Vec::with_capacity(cnt_blocks);
+        for i in 0..cnt_blocks {
             let blk = Block::new(i, Vec::new(), blocksize * i, false);
             edges.push(blk);
         }
 
-        Decoder {
-            total_length: len,
+        let mut decoder = Decoder {
             number_of_chunks,
             unknown_chunks: number_of_chunks,
             cnt_received_drops: 0,
             blocks: edges,
-            data,
+            store: Store {
+                data: storage,
+                blocksize,
+                cnt_stored,
+                cnt_source: number_of_chunks,
+                spill: vec![0; (cnt_blocks - cnt_stored) * blocksize],
+            },
+            droplets: Vec::new(),
+            free: Vec::new(),
+            pending: Vec::new(),
+            recovered: Vec::new(),
             blocksize,
-            dist: Uniform::new(0, number_of_chunks),
-        }
-    }
-
-    fn process_droplet(&mut self, droplet: RxDroplet) {
-        let mut drops: Vec<RxDroplet> = Vec::new();
-        drops.push(droplet);
-        while let Some(drop) = drops.pop() {
-            let edges = drop.edges_idx.clone();
-            // TODO: Maybe add shortcut for the first wave of
-            // systematic codes, reduce overhead
-
-            for ed in edges {
-                // the list is edited, hence we copy first
-                let block = self.blocks.get_mut(ed).unwrap();
-                if block.is_known {
-                    let mut b_drop = drop.clone();
-                    xor_bytes(
-                        &mut b_drop.data[..self.blocksize],
-                        &self.data[block.begin_at..],
-                    );
-                    let pos = b_drop.edges_idx.iter().position(|x| x == &ed).unwrap();
-                    b_drop.edges_idx.remove(pos);
-                } else {
-                    block.edges.push(drop.clone());
+            generator: EdgeGenerator::default(),
+            decodertype,
+            cnt_eliminated: 0,
+            next_elimination: number_of_chunks,
+            seen: Seen::new(SEEN_SEEDED_PER_BLOCK * cnt_blocks),
+            cnt_duplicates: 0,
+            cnt_redundant: 0,
+        };
+        for edges_idx in constraints {
+            decoder.add_droplet(RxDroplet {
+                edges_idx: cancel_pairs(edges_idx),
+                data: vec![0; blocksize],
+            });
+        }
+        decoder
+    }
+
+    /// Expands seeded droplets with `generator` instead of the default
+    /// one. It has to match the encoder's.
+    pub fn with_generator(mut self, generator: EdgeGenerator) -> Decoder<S> {
+        self.generator = generator;
+        self
+    }
+
+    /// Returns the storage. It holds the message once decoding has
+    /// finished.
+    pub fn into_storage(self) -> S {
+        self.store.data
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.unknown_chunks == 0
+    }
+
+    /// The decoded message, once decoding has finished.
+    pub fn data(&self) -> Option<&[u8]> {
+        if self.is_finished() {
+            Some(self.store.data.as_ref())
+        } else {
+            None
+        }
+    }
+
+    /// Whether source block `idx` has been recovered.
+    pub fn is_block_known(&self, idx: usize) -> bool {
+        idx < self.number_of_chunks && self.blocks[idx].is_known
+    }
+
+    /// The data of source block `idx` once it has been recovered. The
+    /// last block is cut off at the end of the message.
+    pub fn block(&self, idx: usize) -> Option<&[u8]> {
+        if !self.is_block_known(idx) {
+            return None;
+        }
+        let data = self.store.data.as_ref();
+        let end = cmp::min((idx + 1) * self.blocksize, data.len());
+        Some(&data[idx * self.blocksize..end])
+    }
+
+    /// The indices of the source blocks recovered by the last call to
+    /// `catch`, in the order they were recovered.
+    pub fn recovered_blocks(&self) -> &[usize] {
+        &self.recovered
+    }
+
+    /// The progress so far, as reported by `catch`.
+    pub fn statistics(&self) -> Statistics {
+        Statistics {
+            cnt_droplets: self.cnt_received_drops,
+            cnt_chunks: self.number_of_chunks,
+            overhead: self.cnt_useful() as f32 * 100.0 / self.number_of_chunks as f32,
+            unknown_chunks: self.unknown_chunks,
+            cnt_eliminated: self.cnt_eliminated,
+            cnt_duplicates: self.cnt_duplicates,
+            cnt_redundant: self.cnt_redundant,
+        }
+    }
+
+    /// Reduces a new droplet by the known blocks and peels it. Returns
+    /// false if it covered known blocks only.
+    fn add_droplet(&mut self, mut drop: RxDroplet) -> bool {
+        let RxDroplet { edges_idx, data } = &mut drop;
+        edges_idx.retain(|&ed| {
+            let is_known = self.blocks[ed].is_known;
+            if is_known {
+                xor_bytes(&mut data[..self.blocksize], self.store.block(ed));
+            }
+            !is_known
+        });
+        match drop.edges_idx.len() {
+            0 => return false,
+            1 => self.learn(drop.edges_idx[0], &drop.data),
+            _ => {
+                let id = match self.free.pop() {
+                    Some(id) => id,
+                    None => {
+                        self.droplets.push(RxDroplet {
+                            edges_idx: Vec::new(),
+                            data: Vec::new(),
+                        });
+                        self.droplets.len() - 1
+                    }
+                };
+                for &ed in &drop.edges_idx {
+                    self.blocks[ed].edges.push(id);
                 }
+                self.droplets[id] = drop;
             }
-            if drop.clone().edges_idx.len() == 1 {
-                let first_idx = *drop.edges_idx.clone().get(0).unwrap();
+        }
+        true
+    }
 
-                let block = self.blocks.get_mut(first_idx).unwrap();
+    fn mark_known(&mut self, idx: usize, data: &[u8]) {
+        self.store.write_block(idx, data);
+        self.blocks[idx].is_known = true;
+        if idx < self.number_of_chunks {
+            self.unknown_chunks -= 1;
+            self.recovered.push(idx);
+        }
+    }
 
-                if !block.is_known {
-                    {
-                        let b_drop = &drop;
-                        for i in 0..self.blocksize {
-                            self.data[block.begin_at + i] = b_drop.data[i];
-                        }
-                    }
-                    block.is_known = true;
-                    self.unknown_chunks -= 1;
-
-                    while let Some(mut edge) = block.edges.pop() {
-                        let m_edge = &mut edge;
-
-                        if m_edge.edges_idx.len() == 1 {
-                            drops.push(edge);
-                        } else {
-                            xor_bytes(
-                                &mut m_edge.data[..self.blocksize],
-                                &self.data[block.begin_at..],
-                            );
-
-                            let pos = m_edge
-                                .edges_idx
-                                .iter()
-                                .position(|x| x == &block.idx)
-                                .unwrap();
-                            m_edge.edges_idx.remove(pos);
-                            if m_edge.edges_idx.len() == 1 {
-                                drops.push(edge.clone());
-                            }
-                        }
+    /// Learns block `idx` and reduces the droplets waiting for it. A
+    /// droplet left with a single unknown block teaches that block in
+    /// turn.
+    fn learn(&mut self, idx: usize, data: &[u8]) {
+        if self.blocks[idx].is_known {
+            return;
+        }
+        self.mark_known(idx, data);
+        let mut pending = mem::take(&mut self.pending);
+        pending.push(idx);
+        while let Some(known) = pending.pop() {
+            for id in mem::take(&mut self.blocks[known].edges) {
+                // Indices of freed or reused slots are left behind in
+                // the blocks, skip droplets not covering the block.
+                let drop = &mut self.droplets[id];
+                let pos = match drop.edges_idx.iter().position(|&ed| ed == known) {
+                    Some(pos) => pos,
+                    None => continue,
+                };
+                drop.edges_idx.swap_remove(pos);
+                xor_bytes(&mut drop.data[..self.blocksize], self.store.block(known));
+                if drop.edges_idx.len() == 1 {
+                    let last = drop.edges_idx.pop().unwrap();
+                    let data = mem::take(&mut drop.data);
+                    self.free.push(id);
+                    if !self.blocks[last].is_known {
+                        self.mark_known(last, &data);
+                        pending.push(last);
                     }
                 }
             }
         }
+        self.pending = pending;
+    }
+
+    fn cnt_useful(&self) -> usize {
+        self.cnt_received_drops - self.cnt_duplicates - self.cnt_redundant
+    }
+
+    /// Solves the droplets buffered in the unknown blocks as a linear
+    /// system over GF(2) and feeds every recovered block back into the
+    /// peeling decoder.
+    ///
+    /// The rank of the system grows by at most one per droplet, so a
+    /// failed elimination is retried only after another k/16 useful
+    /// droplets instead of on every one.
+    fn eliminate(&mut self) {
+        let mut columns = vec![None; self.blocks.len()];
+        let mut unknown = Vec::with_capacity(self.unknown_chunks);
+        for block in self.blocks.iter().filter(|b| !b.is_known) {
+            columns[block.idx] = Some(unknown.len());
+            unknown.push(block.idx);
+        }
+
+        // The droplets in the arena cover unknown blocks only, freed
+        // slots cover none.
+        let mut rows = Vec::new();
+        for drop in self.droplets.iter().filter(|d| !d.edges_idx.is_empty()) {
+            let mut row = Equation::new(unknown.len(), drop.data.clone());
+            for &ed in &drop.edges_idx {
+                if let Some(col) = columns[ed] {
+                    row.toggle(col);
+                }
+            }
+            rows.push(row);
+        }
+
+        let solved = gf2::solve(unknown.len(), rows);
+        self.cnt_eliminated += solved.len();
+        for (col, data) in solved {
+            self.learn(unknown[col], &data);
+        }
+        self.next_elimination = self.cnt_useful() + cmp::max(1, self.number_of_chunks / 16);
     }
 
     /// Catches a Droplet
     /// When it is possible to reconstruct a set, the bytes are returned
-    pub fn catch(&mut self, drop: Droplet) -> CatchResult {
+    ///
+    /// # Panics
+    ///
+    /// Panics if the droplet is malformed, see [`Decoder::try_catch`].
+    pub fn catch(&mut self, drop: Droplet) -> CatchResult<'_> {
+        match self.try_catch(drop) {
+            Ok(result) => result,
+            Err(err) => panic!("malformed droplet: {}", err),
+        }
+    }
+
+    /// Like [`Decoder::catch`], but rejects malformed droplets, e.g.
+    /// ones received from an untrusted network, instead of panicking. A
+    /// rejected droplet leaves the decoder unchanged.
+    pub fn try_catch(&mut self, drop: Droplet) -> Result<CatchResult<'_>, DecodeError> {
+        if drop.data.len() != self.blocksize {
+            return Err(DecodeError::LengthMismatch {
+                expected: self.blocksize,
+                actual: drop.data.len(),
+            });
+        }
+        let cnt_blocks = self.blocks.len();
+        match drop.droptype {
+            DropType::Seeded(_, 0) => return Err(DecodeError::ZeroDegree),
+            DropType::Seeded(_, degree) if degree > cnt_blocks => {
+                return Err(DecodeError::DegreeTooLarge { degree, cnt_blocks })
+            }
+            DropType::Edges(idx) if idx >= cnt_blocks => {
+                return Err(DecodeError::IndexOutOfRange { idx, cnt_blocks })
+            }
+            _ => {}
+        }
         self.cnt_received_drops += 1;
-        let sample: Vec<usize> = match drop.droptype {
-            DropType::Seeded(seed, degree) => {
-                get_sample_from_rng_by_seed(seed, self.dist, degree).collect()
+        self.recovered.clear();
+
+        if !self.seen.insert(drop.droptype.clone()) {
+            self.cnt_duplicates += 1;
+        } else {
+            let is_useful = match drop.droptype {
+                // A systematic droplet carries its block as is, copy it
+                // straight into place
+                DropType::Edges(idx) if self.blocks[idx].is_known => false,
+                DropType::Edges(idx) => {
+                    self.learn(idx, &drop.data);
+                    true
+                }
+                DropType::Seeded(seed, degree) => {
+                    let sample = self.generator.sample(seed, cnt_blocks, degree);
+                    self.add_droplet(RxDroplet {
+                        edges_idx: cancel_pairs(sample),
+                        data: drop.data,
+                    })
+                }
+            };
+            if !is_useful {
+                self.cnt_redundant += 1;
+            } else if self.decodertype == DecoderType::Inactivation
+                && self.unknown_chunks > 0
+                && self.cnt_useful() >= self.next_elimination
+            {
+                self.eliminate();
             }
-            DropType::Edges(edges) => vec![edges],
-        };
+        }
+        let stats = self.statistics();
+        if self.is_finished() {
+            Ok(CatchResult::Finished(self.store.data.as_ref(), stats))
+        } else {
+            Ok(CatchResult::Missing(stats))
+        }
+    }
+}
 
-        let rxdrop = RxDroplet {
-            edges_idx: sample,
-            data: drop.data,
-        };
-        self.process_droplet(rxdrop);
-        let stats = Statistics {
-            cnt_droplets: self.cnt_received_drops,
-            cnt_chunks: self.number_of_chunks,
-            overhead: self.cnt_received_drops as f32 * 100.0 / self.number_of_chunks as f32,
-            unknown_chunks: self.unknown_chunks,
-        };
+/// Seeded droplets remembered per block, see [`Statistics::cnt_duplicates`].
+const SEEN_SEEDED_PER_BLOCK: usize = 2;
+
+/// The droplets received so far. There are at most k systematic ones,
+/// of the seeded ones only the latest `cap` are kept.
+struct Seen {
+    types: HashSet<DropType>,
+    // The remembered seeded droplets, oldest first
+    seeded: VecDeque<DropType>,
+    cap: usize,
+}
+
+impl Seen {
+    fn new(cap: usize) -> Seen {
+        Seen {
+            types: HashSet::new(),
+            seeded: VecDeque::new(),
+            cap,
+        }
+    }
+
+    /// Remembers `droptype`. Returns false if it is already known.
+    fn insert(&mut self, droptype: DropType) -> bool {
+        if !self.types.insert(droptype.clone()) {
+            return false;
+        }
+        if let DropType::Seeded(..) = droptype {
+            self.seeded.push_back(droptype);
+            if self.seeded.len() > self.cap {
+                let oldest = self.seeded.pop_front().unwrap();
+                self.types.remove(&oldest);
+            }
+        }
+        true
+    }
+
+    fn len(&self) -> usize {
+        self.types.len()
+    }
+
+    /// The systematic droplets, then the seeded ones from the oldest
+    /// on, so that inserting them again keeps the same ones.
+    fn iter(&self) -> impl Iterator<Item = &DropType> {
+        self.types
+            .iter()
+            .filter(|droptype| matches!(droptype, DropType::Edges(_)))
+            .chain(self.seeded.iter())
+    }
+}
+
+/// The recovered blocks.
+struct Store<S> {
+    data: S,
+    blocksize: usize,
+    // Blocks from `cnt_stored` on, i.e. the last partial source block
+    // and the auxiliary blocks, don't fit into the storage.
+    cnt_stored: usize,
+    cnt_source: usize,
+    spill: Vec<u8>,
+}
+
+impl<S: AsRef<[u8]> + AsMut<[u8]>> Store<S> {
+    fn block(&self, idx: usize) -> &[u8] {
+        if idx < self.cnt_stored {
+            &self.data.as_ref()[idx * self.blocksize..(idx + 1) * self.blocksize]
+        } else {
+            let begin = (idx - self.cnt_stored) * self.blocksize;
+            &self.spill[begin..begin + self.blocksize]
+        }
+    }
 
-        if self.unknown_chunks == 0 {
-            let mut result = Vec::with_capacity(self.total_length);
-            for i in 0..self.total_length {
-                // TODO: we should be able to do that without copying
-                result.push(self.data[i]);
+    fn write_block(&mut self, idx: usize, data: &[u8]) {
+        let data = &data[..self.blocksize];
+        if idx < self.cnt_stored {
+            self.data.as_mut()[idx * self.blocksize..(idx + 1) * self.blocksize]
+                .copy_from_slice(data);
+        } else {
+            let begin = (idx - self.cnt_stored) * self.blocksize;
+            self.spill[begin..begin + self.blocksize].copy_from_slice(data);
+            if idx < self.cnt_source {
+                // The last source block is cut off at the end of the
+                // message.
+                let storage = self.data.as_mut();
+                let rest = storage.len() - idx * self.blocksize;
+                storage[idx * self.blocksize..].copy_from_slice(&data[..rest]);
             }
-            CatchResult::Finished(result, stats)
+        }
+    }
+}
+
+// A block covered twice by a droplet cancels out of its data.
+fn cancel_pairs(mut edges: Vec<usize>) -> Vec<usize> {
+    edges.sort_unstable();
+    let mut result: Vec<usize> = Vec::with_capacity(edges.len());
+    for ed in edges {
+        if result.last() == Some(&ed) {
+            result.pop();
         } else {
-            CatchResult::Missing(stats)
+            result.push(ed);
         }
     }
+    result
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecoderType {
+    /// Pure peeling (belief propagation). Cheap, but stalls as soon as
+    /// no droplet with a single unknown block is left.
+    Peeling,
+    /// Peeling first. Once it stalls after at least k droplets, the
+    /// buffered droplets are solved with Gaussian elimination, which
+    /// recovers every block the received droplets determine. If blocks
+    /// remain unknown, the elimination is retried after every k/16
+    /// further useful droplets.
+    /// This lowers the overhead at small k at the cost of CPU time.
+    Inactivation,
 }
 
+/// Why [`Decoder::try_catch`] rejected a droplet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The payload is not exactly one block long.
+    LengthMismatch { expected: usize, actual: usize },
+    /// A systematic droplet names a block the message does not have.
+    IndexOutOfRange { idx: usize, cnt_blocks: usize },
+    /// A seeded droplet covers no block at all.
+    ZeroDegree,
+    /// A seeded droplet covers more blocks than the message has.
+    DegreeTooLarge { degree: usize, cnt_blocks: usize },
+    /// A tagged droplet names a source block the object does not have.
+    UnknownSourceBlock {
+        sbn: usize,
+        cnt_source_blocks: usize,
+    },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::LengthMismatch { expected, actual } => write!(
+                f,
+                "payload of {} bytes, expected {} bytes",
+                actual, expected
+            ),
+            DecodeError::IndexOutOfRange { idx, cnt_blocks } => {
+                write!(f, "block {} out of range, there are {}", idx, cnt_blocks)
+            }
+            DecodeError::ZeroDegree => write!(f, "droplet of degree zero"),
+            DecodeError::DegreeTooLarge { degree, cnt_blocks } => write!(
+                f,
+                "droplet of degree {}, there are {} blocks",
+                degree, cnt_blocks
+            ),
+            DecodeError::UnknownSourceBlock {
+                sbn,
+                cnt_source_blocks,
+            } => write!(
+                f,
+                "source block {} out of range, there are {}",
+                sbn, cnt_source_blocks
+            ),
+        }
+    }
+}
+
+impl error::Error for DecodeError {}
+
 #[derive(Debug)]
-pub enum CatchResult {
-    Finished(Vec<u8>, Statistics),
+pub enum CatchResult<'a> {
+    /// The decoded message, borrowed from the decoder.
+    Finished(&'a [u8], Statistics),
     Missing(Statistics),
 }
@@ -0,0 +1,426 @@
+//! Saving and restoring the state of a [`Decoder`].
+use super::{Decoder, DecoderType};
+use crate::{
+    droplet::{DropType, RxDroplet},
+    header::EdgeGenerator,
+    wire::{crc32, crc32_update, read_u32, read_u64},
+};
+use std::{
+    convert::TryFrom,
+    error, fmt,
+    io::{self, Read, Write},
+};
+
+const MAGIC: &[u8; 4] = b"FCDS";
+
+/// The current version of the snapshot format.
+pub const SNAPSHOT_VERSION: u8 = 2;
+
+/// The most memory [`Decoder::restore`] and [`Decoder::restore_into`]
+/// allocate for the blocks of a snapshot, 4 GiB. The snapshot only
+/// holds the known blocks, so it cannot bound the memory for the
+/// unknown ones. Use [`Decoder::restore_with_limit`] for larger
+/// messages.
+pub const DEFAULT_RESTORE_LIMIT: u64 = 1 << 32;
+
+impl<S: AsRef<[u8]> + AsMut<[u8]>> Decoder<S> {
+    /// Writes the full state of the decoder to `writer`: the known
+    /// blocks, the droplets waiting for unknown blocks, the parameters
+    /// and the counters. [`Decoder::restore`] picks up from there.
+    ///
+    /// A snapshot is laid out as follows, all integers big-endian:
+    ///
+    /// ```text
+    /// size  field
+    /// 4     magic "FCDS"
+    /// 1     format version
+    /// 8     message length
+    /// 4     blocksize
+    /// 4     number of auxiliary blocks
+    /// 1     edge generator id
+    /// 1     decoder type, 0 peeling, 1 inactivation
+    /// 8     droplets received
+    /// 8     blocks eliminated
+    /// 8     duplicate droplets
+    /// 8     redundant droplets
+    /// 8     useful droplets at which to eliminate next
+    /// n     bitmap of the known blocks, one bit per block, LSB first
+    /// n     data of every known block, blocksize bytes each
+    /// 4     number of pending droplets, each:
+    ///         4  degree
+    ///         4  every block index
+    ///         n  data, blocksize bytes
+    /// 8     number of droplets seen, the seeded ones oldest first, each:
+    ///         1  0 seeded, 1 edges
+    ///         8  seed, or 4 block index
+    ///         4  degree, seeded only
+    /// 4     CRC-32 of everything before
+    /// ```
+    ///
+    /// The snapshot is written section by section, it is never held in
+    /// memory as a whole. A block size, block count or degree that does
+    /// not fit into its field fails with `io::ErrorKind::InvalidInput`.
+    pub fn save<W: Write>(&self, writer: W) -> io::Result<()> {
+        let cnt_blocks = self.blocks.len();
+        let mut out = CrcWriter::new(writer);
+        out.write_all(MAGIC)?;
+        out.write_all(&[SNAPSHOT_VERSION])?;
+        out.write_all(&(self.store.data.as_ref().len() as u64).to_be_bytes())?;
+        out.write_u32(self.blocksize)?;
+        out.write_u32(cnt_blocks - self.number_of_chunks)?;
+        out.write_all(&[self.generator.id()])?;
+        out.write_all(&[match self.decodertype {
+            DecoderType::Peeling => 0,
+            DecoderType::Inactivation => 1,
+        }])?;
+        for &cnt in &[
+            self.cnt_received_drops,
+            self.cnt_eliminated,
+            self.cnt_duplicates,
+            self.cnt_redundant,
+            self.next_elimination,
+        ] {
+            out.write_all(&(cnt as u64).to_be_bytes())?;
+        }
+
+        let mut bitmap = vec![0; cnt_blocks.div_ceil(8)];
+        for block in self.blocks.iter().filter(|b| b.is_known) {
+            bitmap[block.idx / 8] |= 1 << (block.idx % 8);
+        }
+        out.write_all(&bitmap)?;
+        for block in self.blocks.iter().filter(|b| b.is_known) {
+            out.write_all(self.store.block(block.idx))?;
+        }
+
+        let pending = || self.droplets.iter().filter(|d| !d.edges_idx.is_empty());
+        out.write_u32(pending().count())?;
+        for drop in pending() {
+            out.write_u32(drop.edges_idx.len())?;
+            for &ed in &drop.edges_idx {
+                out.write_u32(ed)?;
+            }
+            out.write_all(&drop.data[..self.blocksize])?;
+        }
+
+        out.write_all(&(self.seen.len() as u64).to_be_bytes())?;
+        for droptype in self.seen.iter() {
+            match *droptype {
+                DropType::Seeded(seed, degree) => {
+                    out.write_all(&[0])?;
+                    out.write_all(&seed.to_be_bytes())?;
+                    out.write_u32(degree)?;
+                }
+                DropType::Edges(idx) => {
+                    out.write_all(&[1])?;
+                    out.write_u32(idx)?;
+                }
+            }
+        }
+        out.finish()
+    }
+
+    /// Restores a decoder saved with [`Decoder::save`] that writes into
+    /// `storage`, which has to be as long as the message. Blocks the
+    /// decoder knew are written into `storage` again. Auxiliary blocks
+    /// of more than [`DEFAULT_RESTORE_LIMIT`] bytes are rejected.
+    pub fn restore_into<R: Read>(mut reader: R, storage: S) -> Result<Decoder<S>, SnapshotError> {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
+        let body = check(&buf)?;
+        let (len, spill) = check_size(body)?;
+        check_limit(spill, DEFAULT_RESTORE_LIMIT)?;
+        if len != storage.as_ref().len() as u64 {
+            return Err(SnapshotError::LengthMismatch {
+                expected: len,
+                actual: storage.as_ref().len() as u64,
+            });
+        }
+        parse(Cursor::new(&body[13..]), storage)
+    }
+}
+
+impl Decoder {
+    /// Restores a decoder saved with [`Decoder::save`]. Snapshots whose
+    /// blocks take more than [`DEFAULT_RESTORE_LIMIT`] bytes are
+    /// rejected.
+    pub fn restore<R: Read>(reader: R) -> Result<Decoder, SnapshotError> {
+        Decoder::restore_with_limit(reader, DEFAULT_RESTORE_LIMIT)
+    }
+
+    /// Like [`Decoder::restore`], but rejects snapshots whose blocks
+    /// take more than `limit` bytes instead.
+    pub fn restore_with_limit<R: Read>(
+        mut reader: R,
+        limit: u64,
+    ) -> Result<Decoder, SnapshotError> {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
+        let body = check(&buf)?;
+        let (len, spill) = check_size(body)?;
+        check_limit(len.checked_add(spill).ok_or(SnapshotError::Corrupt)?, limit)?;
+        let len = usize::try_from(len).map_err(|_| SnapshotError::Corrupt)?;
+        parse(Cursor::new(&body[13..]), vec![0; len])
+    }
+}
+
+// Checks magic, version and checksum, and returns the snapshot without
+// the checksum.
+fn check(buf: &[u8]) -> Result<&[u8], SnapshotError> {
+    if buf.len() < MAGIC.len() + 1 + 8 + 4 {
+        return Err(SnapshotError::Corrupt);
+    }
+    if &buf[..4] != MAGIC {
+        return Err(SnapshotError::BadMagic);
+    }
+    if buf[4] != SNAPSHOT_VERSION {
+        return Err(SnapshotError::UnsupportedVersion(buf[4]));
+    }
+    let (body, crc) = buf.split_at(buf.len() - 4);
+    if crc32(body) != read_u32(crc) {
+        return Err(SnapshotError::ChecksumMismatch);
+    }
+    Ok(body)
+}
+
+// Returns the message length and the bytes of the blocks not stored in
+// the message, once the blocks they make up fit into the bitmap of the
+// snapshot. The decoder allocates per block, so this bounds the
+// allocations that do not depend on the block size by the bytes read.
+fn check_size(body: &[u8]) -> Result<(u64, u64), SnapshotError> {
+    let mut cur = Cursor::new(&body[5..]);
+    let len = cur.u64()?;
+    let blocksize = cur.u32()? as u64;
+    let cnt_aux = cur.u32()? as u64;
+    if blocksize == 0 {
+        return Err(SnapshotError::Corrupt);
+    }
+    let cnt_blocks = len
+        .div_ceil(blocksize)
+        .checked_add(cnt_aux)
+        .ok_or(SnapshotError::Corrupt)?;
+    // Generator, decoder type and the counters precede the bitmap
+    if 2 + 5 * 8 + cnt_blocks.div_ceil(8) > cur.remaining() as u64 {
+        return Err(SnapshotError::Corrupt);
+    }
+    // Like `Store::spill`, which holds the partial last block, too
+    let spill = (cnt_blocks - len / blocksize)
+        .checked_mul(blocksize)
+        .ok_or(SnapshotError::Corrupt)?;
+    Ok((len, spill))
+}
+
+fn check_limit(needed: u64, limit: u64) -> Result<(), SnapshotError> {
+    if needed > limit {
+        return Err(SnapshotError::TooLarge { needed, limit });
+    }
+    Ok(())
+}
+
+// Everything after the message length. The checksum matched, but the
+// contents are still checked, so a snapshot of a buggy writer cannot
+// make the decoder panic later.
+fn parse<S: AsRef<[u8]> + AsMut<[u8]>>(
+    mut cur: Cursor,
+    storage: S,
+) -> Result<Decoder<S>, SnapshotError> {
+    let blocksize = cur.u32()? as usize;
+    let cnt_aux = cur.u32()? as usize;
+    let generator = EdgeGenerator::from_id(cur.u8()?).ok_or(SnapshotError::Corrupt)?;
+    let decodertype = match cur.u8()? {
+        0 => DecoderType::Peeling,
+        1 => DecoderType::Inactivation,
+        _ => return Err(SnapshotError::Corrupt),
+    };
+    if blocksize == 0 {
+        return Err(SnapshotError::Corrupt);
+    }
+    let mut dec = Decoder::with_constraints(storage, blocksize, cnt_aux, Vec::new(), decodertype)
+        .with_generator(generator);
+    dec.cnt_received_drops = cur.u64()? as usize;
+    dec.cnt_eliminated = cur.u64()? as usize;
+    dec.cnt_duplicates = cur.u64()? as usize;
+    dec.cnt_redundant = cur.u64()? as usize;
+    dec.next_elimination = cur.u64()? as usize;
+    let cnt_useless = dec.cnt_duplicates.checked_add(dec.cnt_redundant);
+    if cnt_useless.is_none_or(|cnt| cnt > dec.cnt_received_drops) {
+        return Err(SnapshotError::Corrupt);
+    }
+
+    let cnt_blocks = dec.blocks.len();
+    let bitmap = cur.bytes(cnt_blocks.div_ceil(8))?;
+    for idx in 0..cnt_blocks {
+        if bitmap[idx / 8] & (1 << (idx % 8)) != 0 {
+            let data = cur.bytes(blocksize)?;
+            dec.mark_known(idx, data);
+        }
+    }
+    dec.recovered.clear();
+
+    for _ in 0..cur.u32()? {
+        let degree = cur.u32()? as usize;
+        if degree < 2 || degree > cnt_blocks || degree > cur.remaining() / 4 {
+            return Err(SnapshotError::Corrupt);
+        }
+        let mut edges_idx = Vec::with_capacity(degree);
+        for _ in 0..degree {
+            let ed = cur.u32()? as usize;
+            if ed >= cnt_blocks || dec.blocks[ed].is_known || edges_idx.contains(&ed) {
+                return Err(SnapshotError::Corrupt);
+            }
+            edges_idx.push(ed);
+        }
+        for &ed in &edges_idx {
+            dec.blocks[ed].edges.push(dec.droplets.len());
+        }
+        let data = cur.bytes(blocksize)?.to_vec();
+        dec.droplets.push(RxDroplet { edges_idx, data });
+    }
+
+    for _ in 0..cur.u64()? {
+        let droptype = match cur.u8()? {
+            0 => DropType::Seeded(cur.u64()?, cur.u32()? as usize),
+            1 => DropType::Edges(cur.u32()? as usize),
+            _ => return Err(SnapshotError::Corrupt),
+        };
+        dec.seen.insert(droptype);
+    }
+    if !cur.is_empty() {
+        return Err(SnapshotError::Corrupt);
+    }
+    Ok(dec)
+}
+
+// Passes everything on to a buffered `inner` and appends the CRC-32 of
+// it in `finish`.
+struct CrcWriter<W: Write> {
+    inner: io::BufWriter<W>,
+    crc: u32,
+}
+
+impl<W: Write> CrcWriter<W> {
+    fn new(inner: W) -> Self {
+        CrcWriter {
+            inner: io::BufWriter::new(inner),
+            crc: 0,
+        }
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.crc = crc32_update(self.crc, buf);
+        self.inner.write_all(buf)
+    }
+
+    fn write_u32(&mut self, n: usize) -> io::Result<()> {
+        let n = u32::try_from(n).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} does not fit into a snapshot", n),
+            )
+        })?;
+        self.write_all(&n.to_be_bytes())
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        self.inner.write_all(&self.crc.to_be_bytes())?;
+        self.inner.flush()
+    }
+}
+
+struct Cursor<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Cursor<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Cursor { buf }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    fn remaining(&self) -> usize {
+        self.buf.len()
+    }
+
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8], SnapshotError> {
+        if self.buf.len() < n {
+            return Err(SnapshotError::Corrupt);
+        }
+        let (head, tail) = self.buf.split_at(n);
+        self.buf = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, SnapshotError> {
+        Ok(read_u32(self.bytes(4)?))
+    }
+
+    fn u64(&mut self) -> Result<u64, SnapshotError> {
+        Ok(read_u64(self.bytes(8)?))
+    }
+}
+
+/// Why a snapshot could not be restored.
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// Reading the snapshot failed.
+    Io(io::Error),
+    /// The input is not a decoder snapshot.
+    BadMagic,
+    /// The snapshot was written in a format version this crate does not
+    /// understand.
+    UnsupportedVersion(u8),
+    /// The checksum does not match, the snapshot was damaged.
+    ChecksumMismatch,
+    /// The snapshot is truncated or inconsistent.
+    Corrupt,
+    /// The storage does not have the length of the message.
+    LengthMismatch { expected: u64, actual: u64 },
+    /// Restoring would allocate `needed` bytes for the blocks, more
+    /// than the `limit`.
+    TooLarge { needed: u64, limit: u64 },
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::Io(err) => write!(f, "reading the snapshot failed: {}", err),
+            SnapshotError::BadMagic => write!(f, "not a decoder snapshot"),
+            SnapshotError::UnsupportedVersion(v) => {
+                write!(f, "unsupported snapshot version {}", v)
+            }
+            SnapshotError::ChecksumMismatch => write!(f, "snapshot checksum mismatch"),
+            SnapshotError::Corrupt => write!(f, "corrupt snapshot"),
+            SnapshotError::LengthMismatch { expected, actual } => write!(
+                f,
+                "storage of {} bytes for a message of {} bytes",
+                actual, expected
+            ),
+            SnapshotError::TooLarge { needed, limit } => write!(
+                f,
+                "restoring needs {} bytes, more than the limit of {}",
+                needed, limit
+            ),
+        }
+    }
+}
+
+impl error::Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            SnapshotError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(err: io::Error) -> Self {
+        SnapshotError::Io(err)
+    }
+}
@@ -0,0 +1,128 @@
+//! Degree distributions for the LT [`Encoder`](crate::encoder::Encoder).
+//!
+//! The encoder draws the degree of every droplet from a
+//! [`DegreeDistribution`]. [`Soliton`](crate::soliton::Soliton) provides
+//! the Ideal and Robust Soliton distributions, [`Tabulated`] takes any
+//! distribution given as a probability vector, e.g. a truncated or
+//! empirically tuned one.
+//!
+//! # Example
+//!
+//! ```
+//! use fountaincode::{
+//!     distribution::Tabulated,
+//!     encoder::{Encoder, EncoderType},
+//! };
+//!
+//! // Degrees 1, 2 and 4 only.
+//! let dist = Tabulated::new(vec![0.1, 0.5, 0.0, 0.4]).unwrap();
+//! let msg: Vec<u8> = (0..255).collect();
+//! let mut enc = Encoder::with_distribution(msg, 64, EncoderType::Random, dist);
+//! let drop = enc.drop();
+//! ```
+use rand::Rng;
+use std::{error, fmt};
+
+/// The distribution of droplet degrees.
+pub trait DegreeDistribution {
+    /// Draws a degree, at least 1 and at most `max_degree`.
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> usize;
+
+    /// The probability of drawing `degree`.
+    fn pmf(&self, degree: usize) -> f32;
+
+    /// The largest degree with a non-zero probability.
+    fn max_degree(&self) -> usize;
+}
+
+/// A degree distribution given by the probability of every degree.
+#[derive(Debug, Clone)]
+pub struct Tabulated {
+    pmf: Vec<f32>,
+    cdf: Vec<f32>,
+}
+
+impl Tabulated {
+    /// Creates the distribution where degree `d` has probability
+    /// `probabilities[d - 1]`. The probabilities are normalized to sum
+    /// up to 1.
+    pub fn new(probabilities: Vec<f32>) -> Result<Self, DistributionError> {
+        if let Some(degree) = probabilities
+            .iter()
+            .position(|p| !(p.is_finite() && *p >= 0.0))
+        {
+            return Err(DistributionError::InvalidProbability {
+                degree: degree + 1,
+                probability: probabilities[degree],
+            });
+        }
+        let total: f64 = probabilities.iter().map(|&p| p as f64).sum();
+        if total <= 0.0 {
+            return Err(DistributionError::ZeroTotal);
+        }
+
+        let mut pmf: Vec<f32> = probabilities
+            .iter()
+            .map(|&p| (p as f64 / total) as f32)
+            .collect();
+        while pmf.last() == Some(&0.0) {
+            pmf.pop();
+        }
+        let mut sum = 0.0_f64;
+        let cdf = pmf
+            .iter()
+            .map(|&p| {
+                sum += p as f64;
+                sum as f32
+            })
+            .collect();
+        Ok(Tabulated { pmf, cdf })
+    }
+}
+
+impl DegreeDistribution for Tabulated {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        let u = rng.gen::<f32>() * self.cdf[self.cdf.len() - 1];
+        // Degrees of probability zero never come up, their cumulative
+        // probability equals the one before.
+        let idx = self.cdf.partition_point(|&p| p <= u);
+        idx.min(self.cdf.len() - 1) + 1
+    }
+
+    fn pmf(&self, degree: usize) -> f32 {
+        match degree {
+            0 => 0.0,
+            d => self.pmf.get(d - 1).cloned().unwrap_or(0.0),
+        }
+    }
+
+    fn max_degree(&self) -> usize {
+        self.pmf.len()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DistributionError {
+    /// A probability is negative or not finite.
+    InvalidProbability { degree: usize, probability: f32 },
+    /// The probabilities add up to zero.
+    ZeroTotal,
+}
+
+impl fmt::Display for DistributionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DistributionError::InvalidProbability {
+                degree,
+                probability,
+            } => write!(
+                f,
+                "invalid probability {} for degree {}",
+                probability, degree
+            ),
+            DistributionError::ZeroTotal => write!(f, "the probabilities add up to zero"),
+        }
+    }
+}
+
+impl error::Error for DistributionError {}
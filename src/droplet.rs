@@ -1,4 +1,40 @@
-#[derive(Debug)]
+//! Droplets and their binary wire format.
+//!
+//! A [`Droplet`] is serialized as a fixed header followed by the
+//! payload. All integers are big-endian (network byte order).
+//!
+//! ```text
+//! offset  size  field
+//! 0       2     magic, always `b"FC"`
+//! 2       1     format version, currently 1
+//! 3       1     drop type tag, 0 = Seeded, 1 = Edges
+//!
+//! Seeded (tag 0):
+//! 4       8     seed (u64)
+//! 12      4     degree (u32)
+//! 16      4     payload length (u32)
+//! 20      n     payload
+//!
+//! Edges (tag 1):
+//! 4       4     block index (u32)
+//! 8       4     payload length (u32)
+//! 12      n     payload
+//! ```
+use crate::wire::{read_u32, read_u64};
+use std::{error, fmt};
+
+/// Magic bytes at the start of every serialized droplet.
+pub const WIRE_MAGIC: [u8; 2] = *b"FC";
+/// Version of the droplet wire format written by this crate.
+pub const WIRE_VERSION: u8 = 1;
+
+const TAG_SEEDED: u8 = 0;
+const TAG_EDGES: u8 = 1;
+const PREAMBLE_LEN: usize = 4;
+const SEEDED_HEADER_LEN: usize = PREAMBLE_LEN + 8 + 4 + 4;
+const EDGES_HEADER_LEN: usize = PREAMBLE_LEN + 4 + 4;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum DropType {
     /// First is seed, second degree
     Seeded(u64, usize),
@@ -7,7 +43,7 @@ pub enum DropType {
 }
 
 /// A Droplet is created by the Encoder.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Droplet {
     /// The droptype can be based on seed or a list of edges
     pub droptype: DropType,
@@ -19,6 +55,162 @@ impl Droplet {
     pub fn new(droptype: DropType, data: Vec<u8>) -> Droplet {
         Droplet { droptype, data }
     }
+
+    /// Number of bytes the serialized droplet occupies.
+    pub fn encoded_len(&self) -> usize {
+        let header = match self.droptype {
+            DropType::Seeded(..) => SEEDED_HEADER_LEN,
+            DropType::Edges(_) => EDGES_HEADER_LEN,
+        };
+        header + self.data.len()
+    }
+
+    /// Serializes the droplet into `buf` without allocating and
+    /// returns the number of bytes written.
+    pub fn write_into(&self, buf: &mut [u8]) -> Result<usize, WireError> {
+        let needed = self.encoded_len();
+        if buf.len() < needed {
+            return Err(WireError::BufferTooSmall {
+                needed,
+                available: buf.len(),
+            });
+        }
+        let payload_len = to_u32(self.data.len())?;
+
+        buf[..2].copy_from_slice(&WIRE_MAGIC);
+        buf[2] = WIRE_VERSION;
+        let mut pos = PREAMBLE_LEN;
+        match self.droptype {
+            DropType::Seeded(seed, degree) => {
+                let degree = to_u32(degree)?;
+                buf[3] = TAG_SEEDED;
+                buf[pos..pos + 8].copy_from_slice(&seed.to_be_bytes());
+                buf[pos + 8..pos + 12].copy_from_slice(&degree.to_be_bytes());
+                pos += 12;
+            }
+            DropType::Edges(idx) => {
+                let idx = to_u32(idx)?;
+                buf[3] = TAG_EDGES;
+                buf[pos..pos + 4].copy_from_slice(&idx.to_be_bytes());
+                pos += 4;
+            }
+        }
+        buf[pos..pos + 4].copy_from_slice(&payload_len.to_be_bytes());
+        pos += 4;
+        buf[pos..needed].copy_from_slice(&self.data);
+        Ok(needed)
+    }
+
+    /// Serializes the droplet into a freshly allocated buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the degree, block index or payload length does not
+    /// fit into 32 bits. Use [`Droplet::write_into`] to handle that
+    /// case as an error.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![0; self.encoded_len()];
+        self.write_into(&mut buf)
+            .expect("droplet fields exceed the wire format limits");
+        buf
+    }
+
+    /// Parses a droplet previously produced by [`Droplet::to_bytes`] or
+    /// [`Droplet::write_into`]. `buf` must contain exactly one droplet.
+    pub fn from_bytes(buf: &[u8]) -> Result<Droplet, WireError> {
+        check_len(buf, PREAMBLE_LEN)?;
+        if buf[..2] != WIRE_MAGIC {
+            return Err(WireError::BadMagic);
+        }
+        if buf[2] != WIRE_VERSION {
+            return Err(WireError::UnsupportedVersion(buf[2]));
+        }
+
+        let (droptype, pos) = match buf[3] {
+            TAG_SEEDED => {
+                check_len(buf, SEEDED_HEADER_LEN)?;
+                let seed = read_u64(&buf[4..12]);
+                let degree = read_u32(&buf[12..16]) as usize;
+                (DropType::Seeded(seed, degree), 16)
+            }
+            TAG_EDGES => {
+                check_len(buf, EDGES_HEADER_LEN)?;
+                let idx = read_u32(&buf[4..8]) as usize;
+                (DropType::Edges(idx), 8)
+            }
+            tag => return Err(WireError::UnknownDropType(tag)),
+        };
+
+        let payload_len = read_u32(&buf[pos..pos + 4]) as usize;
+        let begin = pos + 4;
+        check_len(buf, begin + payload_len)?;
+        if buf.len() > begin + payload_len {
+            return Err(WireError::TrailingBytes(buf.len() - begin - payload_len));
+        }
+        Ok(Droplet::new(droptype, buf[begin..].to_vec()))
+    }
+}
+
+/// Errors raised while serializing or parsing a [`Droplet`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WireError {
+    /// The input ended before the droplet was complete.
+    Truncated { needed: usize, available: usize },
+    /// The output buffer cannot hold the serialized droplet.
+    BufferTooSmall { needed: usize, available: usize },
+    /// The input does not start with [`WIRE_MAGIC`].
+    BadMagic,
+    /// The input was written with a format version we cannot read.
+    UnsupportedVersion(u8),
+    /// The drop type tag is neither Seeded nor Edges.
+    UnknownDropType(u8),
+    /// The input contains this many bytes after the payload.
+    TrailingBytes(usize),
+    /// A degree, index or length does not fit into 32 bits.
+    ValueTooLarge(usize),
+}
+
+impl fmt::Display for WireError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WireError::Truncated { needed, available } => write!(
+                f,
+                "truncated droplet: need {} bytes, got {}",
+                needed, available
+            ),
+            WireError::BufferTooSmall { needed, available } => write!(
+                f,
+                "buffer too small: need {} bytes, got {}",
+                needed, available
+            ),
+            WireError::BadMagic => write!(f, "not a droplet: bad magic"),
+            WireError::UnsupportedVersion(v) => write!(f, "unsupported droplet version {}", v),
+            WireError::UnknownDropType(t) => write!(f, "unknown drop type tag {}", t),
+            WireError::TrailingBytes(n) => write!(f, "{} trailing bytes after droplet", n),
+            WireError::ValueTooLarge(v) => write!(f, "value {} does not fit into 32 bits", v),
+        }
+    }
+}
+
+impl error::Error for WireError {}
+
+fn check_len(buf: &[u8], needed: usize) -> Result<(), WireError> {
+    if buf.len() < needed {
+        Err(WireError::Truncated {
+            needed,
+            available: buf.len(),
+        })
+    } else {
+        Ok(())
+    }
+}
+
+fn to_u32(value: usize) -> Result<u32, WireError> {
+    if value > u32::MAX as usize {
+        Err(WireError::ValueTooLarge(value))
+    } else {
+        Ok(value as u32)
+    }
 }
 
 #[derive(Debug, Clone)]
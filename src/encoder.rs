@@ -1,14 +1,16 @@
 use crate::{
+    distribution::DegreeDistribution,
     droplet::{DropType, Droplet},
+    header::{CodeVariant, EdgeGenerator, SessionHeader},
+    prng::{derive_seed, SplitMix64},
     soliton::Soliton,
-    xor::xor_bytes,
+    xor::xor_many,
 };
 use rand::{
-    distributions::{Distribution, Uniform},
     rngs::StdRng,
-    {Rng, SeedableRng},
+    {Rng, RngCore, SeedableRng},
 };
-use std::cmp;
+use std::{cmp, marker::PhantomData};
 
 /// Encoder for Luby Transform codes.
 ///
@@ -16,8 +18,8 @@ use std::cmp;
 /// the MTU size.
 ///
 /// There are two encoding modes, Systematic and Random.  The
-/// Systematic encoder first produces a set of the source
-/// symbols. After each symbol is sent once, it switches to Random.
+/// Systematic encoder first sends the source symbols as they are, for
+/// a configurable number of rounds, then it switches to Random.
 ///
 /// # Example
 ///
@@ -33,101 +35,242 @@ use std::cmp;
 ///     println!("droplet {:?}: {:?}", i, enc.next());
 /// }
 /// ```
+///
+/// The message can be any byte buffer `S`, e.g. a memory-mapped file
+/// from the [`mmap`](crate::mmap) module instead of a `Vec<u8>`.
+///
+/// Droplet `n` is drawn from an RNG `R` seeded with the master seed and
+/// `n`, so the same seed produces the same droplets, and
+/// [`Encoder::drop_at`] regenerates any of them. The default
+/// [`SplitMix64`] is frozen, unlike the generators of `rand`, so the
+/// droplets of a seed stay the same across releases:
+///
+/// ```
+/// use fountaincode::encoder::{Encoder, EncoderType};
+///
+/// let msg: Vec<u8> = (0..255).collect();
+/// let mut enc = Encoder::ideal(msg.clone(), 64, EncoderType::Random).with_seed(42);
+/// let drops: Vec<_> = enc.by_ref().take(10).collect();
+///
+/// let again = Encoder::ideal(msg, 64, EncoderType::Random).with_seed(42);
+/// assert_eq!(again.drop_at(7), drops[7]);
+/// ```
 #[derive(Clone)]
-pub struct Encoder {
-    data: Vec<u8>,
+pub struct Encoder<D = Soliton, S = Vec<u8>, R = SplitMix64> {
+    data: S,
     len: usize,
     blocksize: usize,
-    rng: StdRng,
-    dist: Uniform<usize>,
+    seed: u64,
+    rng: PhantomData<R>,
+    generator: EdgeGenerator,
     cnt_blocks: usize,
-    sol: Soliton,
+    sol: D,
     pub cnt: usize,
     encodertype: EncoderType,
+    systematic_rounds: usize,
+    repair_interval: Option<usize>,
 }
 
-impl Encoder {
+impl<S: AsRef<[u8]>> Encoder<Soliton, S> {
+    /// Creates an encoder with a robust soliton distribution and a
+    /// random master seed.
+    ///
+    /// # Panics
+    ///
+    /// Panics on a `blocksize` of zero, invalid Soliton parameters, or
+    /// when drawing droplets of empty `data`.
+    /// [`EncoderBuilder`](crate::builder::EncoderBuilder) reports these
+    /// as errors instead.
     pub fn robust(
-        data: Vec<u8>,
+        data: S,
         blocksize: usize,
         encodertype: EncoderType,
         c: f32,
         spike: Option<usize>,
         delta: f32,
     ) -> Self {
-        let rng = StdRng::from_entropy();
-        let len = data.len();
-        let cnt_blocks = (len + blocksize - 1) / blocksize;
+        let cnt_blocks = data.as_ref().len().div_ceil(blocksize);
         let sol = Soliton::robust(cnt_blocks, c, spike, delta);
+        Encoder::with_distribution(data, blocksize, encodertype, sol)
+    }
+
+    /// Creates an encoder with an ideal soliton distribution and a
+    /// random master seed.
+    ///
+    /// # Panics
+    ///
+    /// Panics on a `blocksize` of zero or when drawing droplets of
+    /// empty `data`.
+    pub fn ideal(data: S, blocksize: usize, encodertype: EncoderType) -> Self {
+        let cnt_blocks = data.as_ref().len().div_ceil(blocksize);
+        let sol = Soliton::ideal(cnt_blocks);
+        Encoder::with_distribution(data, blocksize, encodertype, sol)
+    }
+}
+
+impl<S: AsRef<[u8]>, R> Encoder<Soliton, S, R> {
+    /// Describes this encoding session, so the receiver can build a
+    /// matching `Decoder` with `Decoder::from_header`.
+    pub fn header(&self) -> SessionHeader {
+        let variant = match self.sol {
+            Soliton::Ideal { .. } => CodeVariant::IdealSoliton,
+            Soliton::Robust { c, delta, m, .. } => {
+                CodeVariant::RobustSoliton { c, delta, spike: m }
+            }
+        };
+        SessionHeader::new(self.len, self.blocksize, variant, self.generator)
+    }
+}
+
+impl<D: DegreeDistribution, S: AsRef<[u8]>> Encoder<D, S> {
+    /// Creates an encoder drawing the droplet degrees from `sol`, with a
+    /// random master seed.
+    pub fn with_distribution(data: S, blocksize: usize, encodertype: EncoderType, sol: D) -> Self {
+        let len = data.as_ref().len();
+        let cnt_blocks = len.div_ceil(blocksize);
         Encoder {
             data,
             len,
             blocksize,
-            rng,
-            dist: Uniform::new(0, cnt_blocks),
+            seed: rand::random(),
+            rng: PhantomData,
+            generator: EdgeGenerator::default(),
             cnt_blocks,
             sol,
             cnt: 0,
             encodertype,
+            systematic_rounds: 2,
+            repair_interval: None,
         }
     }
+}
 
-    pub fn ideal(data: Vec<u8>, blocksize: usize, encodertype: EncoderType) -> Self {
-        let rng = StdRng::from_entropy();
-        let len = data.len();
-        let cnt_blocks = (len + blocksize - 1) / blocksize;
-        let sol = Soliton::ideal(cnt_blocks);
-        Self {
-            data,
-            len,
-            blocksize,
-            rng,
-            dist: Uniform::new(0, cnt_blocks),
-            cnt_blocks,
-            sol,
-            cnt: 0,
-            encodertype,
+impl<D: DegreeDistribution, S: AsRef<[u8]>, R: RngCore + SeedableRng> Encoder<D, S, R> {
+    /// Derives the droplets from the master `seed`, so that they can be
+    /// reproduced.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Draws the droplets from RNGs of type `T`, seeded per droplet. The
+    /// master seed is taken from `rng`.
+    pub fn with_rng<T: RngCore + SeedableRng>(self, mut rng: T) -> Encoder<D, S, T> {
+        Encoder {
+            data: self.data,
+            len: self.len,
+            blocksize: self.blocksize,
+            seed: rng.next_u64(),
+            rng: PhantomData,
+            generator: self.generator,
+            cnt_blocks: self.cnt_blocks,
+            sol: self.sol,
+            cnt: self.cnt,
+            encodertype: self.encodertype,
+            systematic_rounds: self.systematic_rounds,
+            repair_interval: self.repair_interval,
         }
     }
 
-    pub fn drop(&mut self) -> Droplet {
-        let mut r = vec![0; self.blocksize];
+    /// The master seed all droplets are derived from.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
 
-        let drop = match self.encodertype {
-            EncoderType::Random => {
-                let degree = self.sol.sample(&mut self.rng);
-                let seed = self.rng.gen::<u64>();
-                let sample = get_sample_from_rng_by_seed(seed, self.dist, degree);
-
-                for k in sample {
-                    let begin = k * self.blocksize;
-                    let end = cmp::min((k + 1) * self.blocksize, self.len);
-                    xor_bytes(&mut r, &self.data[begin..end]);
-                }
-                Droplet::new(DropType::Seeded(seed, degree), r)
-            }
-            EncoderType::Systematic => {
-                let begin = (self.cnt % self.cnt_blocks) * self.blocksize;
-                let end = cmp::min(
-                    ((self.cnt % self.cnt_blocks) + 1) * self.blocksize,
-                    self.len,
-                );
-
-                for (src_dat, drop_dat) in self.data[begin..end].iter().zip(r.iter_mut()) {
-                    *drop_dat = *src_dat;
-                }
-                if (self.cnt + 2) > self.cnt_blocks * 2 {
-                    self.encodertype = EncoderType::Random;
-                }
-                Droplet::new(DropType::Edges(self.cnt % self.cnt_blocks), r)
-            }
-        };
+    /// Expands the seeds of the droplets with `generator` instead of the
+    /// default one, e.g. to talk to receivers of older versions.
+    pub fn with_generator(mut self, generator: EdgeGenerator) -> Self {
+        self.generator = generator;
+        self
+    }
+
+    /// Sends the source blocks `rounds` times before switching to random
+    /// droplets, twice by default. Once is enough on links with little
+    /// loss. Only applies to `EncoderType::Systematic`.
+    pub fn with_systematic_rounds(mut self, rounds: usize) -> Self {
+        self.systematic_rounds = rounds;
+        self
+    }
+
+    /// Sends a random repair droplet after every `interval` source
+    /// blocks of the systematic rounds, instead of only after them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    pub fn with_repair_interval(mut self, interval: usize) -> Self {
+        assert!(interval > 0, "repair interval must not be zero");
+        self.repair_interval = Some(interval);
+        self
+    }
 
+    /// Produces the next droplet, the one with sequence number `cnt`.
+    pub fn drop(&mut self) -> Droplet {
+        let drop = self.drop_at(self.cnt);
         self.cnt += 1;
         drop
     }
+
+    /// Regenerates the droplet with sequence number `n`, the droplet
+    /// `drop` returns when `cnt` is `n`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the message is empty.
+    pub fn drop_at(&self, n: usize) -> Droplet {
+        assert!(self.cnt_blocks > 0, "cannot encode an empty message");
+        let mut r = vec![0; self.blocksize];
+        let data = self.data.as_ref();
+        let systematic = match self.encodertype {
+            EncoderType::Systematic => self.systematic_block(n),
+            EncoderType::Random => None,
+        };
+
+        if let Some(idx) = systematic {
+            let begin = idx * self.blocksize;
+            let end = cmp::min((idx + 1) * self.blocksize, self.len);
+            r[..end - begin].copy_from_slice(&data[begin..end]);
+            return Droplet::new(DropType::Edges(idx), r);
+        }
+
+        // Seeding R with the master seed plus a multiple of n would
+        // start SplitMix64 one step into the stream of droplet n - 1
+        let mut rng = R::seed_from_u64(derive_seed(self.seed, n as u64));
+        // The decoder rejects droplets of more than k blocks
+        let degree = cmp::min(self.sol.sample(&mut rng), self.cnt_blocks);
+        let seed = rng.gen::<u64>();
+        let sample = self.generator.sample(seed, self.cnt_blocks, degree);
+
+        let blocks: Vec<&[u8]> = sample
+            .into_iter()
+            .map(|k| {
+                let begin = k * self.blocksize;
+                let end = cmp::min((k + 1) * self.blocksize, self.len);
+                &data[begin..end]
+            })
+            .collect();
+        xor_many(&mut r, &blocks);
+        Droplet::new(DropType::Seeded(seed, degree), r)
+    }
+
+    /// The source block droplet `n` carries, if it is one of the
+    /// systematic rounds.
+    fn systematic_block(&self, n: usize) -> Option<usize> {
+        let cnt_systematic = match self.repair_interval {
+            Some(interval) if n % (interval + 1) == interval => return None,
+            Some(interval) => n - n / (interval + 1),
+            None => n,
+        };
+        if cnt_systematic < self.systematic_rounds * self.cnt_blocks {
+            Some(cnt_systematic % self.cnt_blocks)
+        } else {
+            None
+        }
+    }
 }
 
+/// Draws `degree` indices from `range` with `StdRng`, the algorithm of
+/// `EdgeGenerator::StdRngUniform`.
 pub fn get_sample_from_rng_by_seed(
     seed: u64,
     range: rand::distributions::Uniform<usize>,
@@ -137,7 +280,9 @@ pub fn get_sample_from_rng_by_seed(
     rng.sample_iter(range).take(degree)
 }
 
-impl Iterator for Encoder {
+impl<D: DegreeDistribution, S: AsRef<[u8]>, R: RngCore + SeedableRng> Iterator
+    for Encoder<D, S, R>
+{
     type Item = Droplet;
     fn next(&mut self) -> Option<Droplet> {
         Some(self.drop())
@@ -147,8 +292,8 @@ impl Iterator for Encoder {
 #[derive(Clone, Debug)]
 pub enum EncoderType {
     /// The first k symbols of a systematic Encoder correspond to the first k source symbols
-    /// In case there is no loss, no repair needed. After the first k symbols are sent, it continous
-    /// like in the Random case.
+    /// In case there is no loss, no repair needed. After the source symbols have been sent for
+    /// the configured number of rounds, it continues like in the Random case.
     Systematic,
     /// Begins immediately with random encoding.
     /// This may be a better choice when used with high-loss channels.
@@ -0,0 +1,73 @@
+// Gaussian elimination over GF(2).
+//
+// Every equation states that the XOR of the unknowns whose bits are
+// set in `coefs` equals `data`. The solver brings the system into
+// reduced row echelon form and returns every unknown that ends up
+// alone in its row, even if the system as a whole is underdetermined.
+use crate::xor::xor_bytes;
+
+pub struct Equation {
+    pub coefs: Vec<u64>,
+    pub data: Vec<u8>,
+}
+
+impl Equation {
+    pub fn new(cols: usize, data: Vec<u8>) -> Self {
+        Equation {
+            coefs: vec![0; cols.div_ceil(64)],
+            data,
+        }
+    }
+
+    pub fn toggle(&mut self, col: usize) {
+        self.coefs[col / 64] ^= 1 << (col % 64);
+    }
+
+    fn is_set(&self, col: usize) -> bool {
+        self.coefs[col / 64] & (1 << (col % 64)) != 0
+    }
+
+    fn add(&mut self, other: &Equation) {
+        for (l, r) in self.coefs.iter_mut().zip(&other.coefs) {
+            *l ^= *r;
+        }
+        xor_bytes(&mut self.data, &other.data);
+    }
+
+    fn weight(&self) -> u32 {
+        self.coefs.iter().map(|w| w.count_ones()).sum()
+    }
+}
+
+/// Solves for as many of the `cols` unknowns as the equations allow.
+/// Returns pairs of unknown index and its value.
+pub fn solve(cols: usize, mut rows: Vec<Equation>) -> Vec<(usize, Vec<u8>)> {
+    let mut pivots = Vec::new();
+    let mut rank = 0;
+
+    for col in 0..cols {
+        let pivot = match (rank..rows.len()).find(|&r| rows[r].is_set(col)) {
+            Some(p) => p,
+            None => continue,
+        };
+        rows.swap(rank, pivot);
+
+        let (head, tail) = rows.split_at_mut(rank);
+        let (pivot_row, tail) = tail.split_first_mut().unwrap();
+        for row in head.iter_mut().chain(tail.iter_mut()) {
+            if row.is_set(col) {
+                row.add(pivot_row);
+            }
+        }
+        pivots.push(col);
+        rank += 1;
+    }
+
+    rows.truncate(rank);
+    pivots
+        .into_iter()
+        .zip(rows)
+        .filter(|(_, row)| row.weight() == 1)
+        .map(|(col, row)| (col, row.data))
+        .collect()
+}
@@ -0,0 +1,369 @@
+//! Self-describing session header.
+//!
+//! The [`SessionHeader`] carries everything a receiver needs to build a
+//! matching [`Decoder`](crate::decoder::Decoder): the object length,
+//! the block size, the code variant with its distribution parameters,
+//! and the edge generator used to expand seeded droplets. It plays the
+//! same role as the object transmission information (OTI) in RaptorQ.
+//!
+//! All integers are big-endian, floats are IEEE 754 single precision.
+//!
+//! ```text
+//! offset  size  field
+//! 0       2     magic, always `b"FH"`
+//! 2       1     format version, currently 1
+//! 3       1     code variant, 0 = Ideal Soliton, 1 = Robust Soliton
+//! 4       8     object length in bytes (u64)
+//! 12      4     block size in bytes (u32)
+//! 16      1     edge generator, 0 = StdRng + Uniform(0, k),
+//!               1 = SplitMix64, 2 = SplitMix64 without replacement
+//!
+//! Robust Soliton (variant 1) only:
+//! 17      4     c (f32)
+//! 21      4     delta (f32)
+//! 25      4     spike position (u32)
+//! ```
+use crate::{
+    encoder::get_sample_from_rng_by_seed,
+    prng::SplitMix64,
+    soliton::{check_robust, SolitonError},
+    wire::{read_u32, read_u64},
+};
+use rand::distributions::Uniform;
+use std::{cmp, collections::HashSet, convert::TryFrom, error, fmt};
+
+/// Magic bytes at the start of every serialized session header.
+pub const HEADER_MAGIC: [u8; 2] = *b"FH";
+/// Version of the session header format written by this crate.
+pub const HEADER_VERSION: u8 = 1;
+
+const VARIANT_IDEAL: u8 = 0;
+const VARIANT_ROBUST: u8 = 1;
+const BASE_LEN: usize = 17;
+const ROBUST_LEN: usize = BASE_LEN + 12;
+
+/// The code variant and the parameters of its degree distribution.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CodeVariant {
+    IdealSoliton,
+    RobustSoliton { c: f32, delta: f32, spike: usize },
+}
+
+/// The algorithm that expands a seed into the block indices of a droplet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EdgeGenerator {
+    /// `rand::rngs::StdRng` seeded with the droplet seed, sampling
+    /// from `Uniform(0, k)` with replacement. `StdRng` may change with
+    /// any `rand` release, so both sides need the same `rand` version.
+    StdRngUniform,
+    /// [`SplitMix64`] seeded with the droplet seed, every index is
+    /// `SplitMix64::below(k)`, with replacement. Frozen across crate
+    /// versions.
+    SplitMix64,
+    /// [`SplitMix64`] seeded with the droplet seed, drawing distinct
+    /// indices with Floyd's algorithm: for `j` in `k - d..k`, take
+    /// `t = below(j + 1)`, or `j` if `t` was already taken. A degree
+    /// above k yields all k blocks. This is the default.
+    #[default]
+    SplitMix64Distinct,
+}
+
+impl EdgeGenerator {
+    /// Expands `seed` into the `degree` block indices of a droplet over
+    /// `cnt_blocks` blocks.
+    pub fn sample(self, seed: u64, cnt_blocks: usize, degree: usize) -> Vec<usize> {
+        match self {
+            EdgeGenerator::StdRngUniform => {
+                get_sample_from_rng_by_seed(seed, Uniform::new(0, cnt_blocks), degree).collect()
+            }
+            EdgeGenerator::SplitMix64 => {
+                let mut rng = SplitMix64::new(seed);
+                (0..degree).map(|_| rng.below(cnt_blocks)).collect()
+            }
+            EdgeGenerator::SplitMix64Distinct => {
+                let mut rng = SplitMix64::new(seed);
+                let degree = cmp::min(degree, cnt_blocks);
+                let mut taken = HashSet::with_capacity(degree);
+                let mut sample = Vec::with_capacity(degree);
+                for j in cnt_blocks - degree..cnt_blocks {
+                    let t = rng.below(j + 1);
+                    let idx = if taken.contains(&t) { j } else { t };
+                    taken.insert(idx);
+                    sample.push(idx);
+                }
+                sample
+            }
+        }
+    }
+
+    pub(crate) fn id(self) -> u8 {
+        match self {
+            EdgeGenerator::StdRngUniform => 0,
+            EdgeGenerator::SplitMix64 => 1,
+            EdgeGenerator::SplitMix64Distinct => 2,
+        }
+    }
+
+    pub(crate) fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(EdgeGenerator::StdRngUniform),
+            1 => Some(EdgeGenerator::SplitMix64),
+            2 => Some(EdgeGenerator::SplitMix64Distinct),
+            _ => None,
+        }
+    }
+}
+
+/// Describes an encoded object so the receiver needs no out-of-band
+/// configuration.
+///
+/// Obtain one from [`Encoder::header`](crate::encoder::Encoder::header),
+/// send it ahead of the droplets, and build the receiver with
+/// [`Decoder::from_header`](crate::decoder::Decoder::from_header).
+///
+/// # Example
+///
+/// ```
+/// use fountaincode::{
+///     decoder::Decoder,
+///     encoder::{Encoder, EncoderType},
+///     header::SessionHeader,
+/// };
+///
+/// let msg: Vec<u8> = (0..255).collect();
+/// let enc = Encoder::robust(msg, 64, EncoderType::Random, 0.2, None, 0.05);
+///
+/// let bytes = enc.header().to_bytes();
+/// let header = SessionHeader::from_bytes(&bytes).unwrap();
+/// let dec = Decoder::from_header(&header);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionHeader {
+    len: usize,
+    blocksize: usize,
+    variant: CodeVariant,
+    generator: EdgeGenerator,
+}
+
+impl SessionHeader {
+    pub(crate) fn new(
+        len: usize,
+        blocksize: usize,
+        variant: CodeVariant,
+        generator: EdgeGenerator,
+    ) -> Self {
+        SessionHeader {
+            len,
+            blocksize,
+            variant,
+            generator,
+        }
+    }
+
+    /// Total length of the object in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the object is empty. Valid headers never are.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Size of a block, and of every droplet payload, in bytes.
+    pub fn blocksize(&self) -> usize {
+        self.blocksize
+    }
+
+    /// Number of source blocks the object is split into.
+    pub fn cnt_blocks(&self) -> usize {
+        self.len.div_ceil(self.blocksize)
+    }
+
+    pub fn variant(&self) -> CodeVariant {
+        self.variant
+    }
+
+    pub fn generator(&self) -> EdgeGenerator {
+        self.generator
+    }
+
+    /// Number of bytes the serialized header occupies.
+    pub fn encoded_len(&self) -> usize {
+        match self.variant {
+            CodeVariant::IdealSoliton => BASE_LEN,
+            CodeVariant::RobustSoliton { .. } => ROBUST_LEN,
+        }
+    }
+
+    /// Serializes the header into `buf` and returns the number of
+    /// bytes written.
+    pub fn write_into(&self, buf: &mut [u8]) -> Result<usize, HeaderError> {
+        let needed = self.encoded_len();
+        if buf.len() < needed {
+            return Err(HeaderError::BufferTooSmall {
+                needed,
+                available: buf.len(),
+            });
+        }
+        let blocksize = u32::try_from(self.blocksize)
+            .map_err(|_| HeaderError::ValueTooLarge(self.blocksize))?;
+
+        buf[..2].copy_from_slice(&HEADER_MAGIC);
+        buf[2] = HEADER_VERSION;
+        buf[4..12].copy_from_slice(&(self.len as u64).to_be_bytes());
+        buf[12..16].copy_from_slice(&blocksize.to_be_bytes());
+        buf[16] = self.generator.id();
+        match self.variant {
+            CodeVariant::IdealSoliton => buf[3] = VARIANT_IDEAL,
+            CodeVariant::RobustSoliton { c, delta, spike } => {
+                let spike = u32::try_from(spike).map_err(|_| HeaderError::ValueTooLarge(spike))?;
+                buf[3] = VARIANT_ROBUST;
+                buf[17..21].copy_from_slice(&c.to_bits().to_be_bytes());
+                buf[21..25].copy_from_slice(&delta.to_bits().to_be_bytes());
+                buf[25..29].copy_from_slice(&spike.to_be_bytes());
+            }
+        }
+        Ok(needed)
+    }
+
+    /// Serializes the header into a freshly allocated buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the block size or spike position does not fit into
+    /// 32 bits.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![0; self.encoded_len()];
+        self.write_into(&mut buf)
+            .expect("header fields exceed the wire format limits");
+        buf
+    }
+
+    /// Parses and validates a serialized header. `buf` must contain
+    /// exactly one header.
+    pub fn from_bytes(buf: &[u8]) -> Result<SessionHeader, HeaderError> {
+        check_len(buf, BASE_LEN)?;
+        if buf[..2] != HEADER_MAGIC {
+            return Err(HeaderError::BadMagic);
+        }
+        if buf[2] != HEADER_VERSION {
+            return Err(HeaderError::UnsupportedVersion(buf[2]));
+        }
+
+        let len = read_u64(&buf[4..12]);
+        let len = usize::try_from(len).map_err(|_| HeaderError::LengthOverflow(len))?;
+        if len == 0 {
+            return Err(HeaderError::EmptyObject);
+        }
+        let blocksize = read_u32(&buf[12..16]) as usize;
+        if blocksize == 0 {
+            return Err(HeaderError::ZeroBlocksize);
+        }
+        let generator =
+            EdgeGenerator::from_id(buf[16]).ok_or(HeaderError::UnknownGenerator(buf[16]))?;
+
+        let variant = match buf[3] {
+            VARIANT_IDEAL => CodeVariant::IdealSoliton,
+            VARIANT_ROBUST => {
+                check_len(buf, ROBUST_LEN)?;
+                let c = f32::from_bits(read_u32(&buf[17..21]));
+                let delta = f32::from_bits(read_u32(&buf[21..25]));
+                let spike = read_u32(&buf[25..29]) as usize;
+                check_robust(len.div_ceil(blocksize), c, Some(spike), delta)?;
+                CodeVariant::RobustSoliton { c, delta, spike }
+            }
+            v => return Err(HeaderError::UnknownVariant(v)),
+        };
+
+        let header = SessionHeader::new(len, blocksize, variant, generator);
+        if buf.len() > header.encoded_len() {
+            return Err(HeaderError::TrailingBytes(buf.len() - header.encoded_len()));
+        }
+        Ok(header)
+    }
+}
+
+/// Errors raised while serializing or parsing a [`SessionHeader`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeaderError {
+    /// The input ended before the header was complete.
+    Truncated { needed: usize, available: usize },
+    /// The output buffer cannot hold the serialized header.
+    BufferTooSmall { needed: usize, available: usize },
+    /// The input does not start with [`HEADER_MAGIC`].
+    BadMagic,
+    /// The input was written with a format version we cannot read.
+    UnsupportedVersion(u8),
+    /// The code variant tag is not known.
+    UnknownVariant(u8),
+    /// The edge generator id is not known.
+    UnknownGenerator(u8),
+    /// The object length is zero.
+    EmptyObject,
+    /// The block size is zero.
+    ZeroBlocksize,
+    /// The object length does not fit into `usize` on this platform.
+    LengthOverflow(u64),
+    /// The input contains this many bytes after the header.
+    TrailingBytes(usize),
+    /// A block size or spike position does not fit into 32 bits.
+    ValueTooLarge(usize),
+    /// The Robust Soliton parameters are invalid.
+    Soliton(SolitonError),
+}
+
+impl fmt::Display for HeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HeaderError::Truncated { needed, available } => write!(
+                f,
+                "truncated header: need {} bytes, got {}",
+                needed, available
+            ),
+            HeaderError::BufferTooSmall { needed, available } => write!(
+                f,
+                "buffer too small: need {} bytes, got {}",
+                needed, available
+            ),
+            HeaderError::BadMagic => write!(f, "not a session header: bad magic"),
+            HeaderError::UnsupportedVersion(v) => write!(f, "unsupported header version {}", v),
+            HeaderError::UnknownVariant(v) => write!(f, "unknown code variant {}", v),
+            HeaderError::UnknownGenerator(g) => write!(f, "unknown edge generator {}", g),
+            HeaderError::EmptyObject => write!(f, "object length is zero"),
+            HeaderError::ZeroBlocksize => write!(f, "block size is zero"),
+            HeaderError::LengthOverflow(len) => {
+                write!(f, "object length {} does not fit into usize", len)
+            }
+            HeaderError::TrailingBytes(n) => write!(f, "{} trailing bytes after header", n),
+            HeaderError::ValueTooLarge(v) => write!(f, "value {} does not fit into 32 bits", v),
+            HeaderError::Soliton(err) => write!(f, "invalid Robust Soliton parameters: {}", err),
+        }
+    }
+}
+
+impl error::Error for HeaderError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            HeaderError::Soliton(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<SolitonError> for HeaderError {
+    fn from(err: SolitonError) -> Self {
+        HeaderError::Soliton(err)
+    }
+}
+
+fn check_len(buf: &[u8], needed: usize) -> Result<(), HeaderError> {
+    if buf.len() < needed {
+        Err(HeaderError::Truncated {
+            needed,
+            available: buf.len(),
+        })
+    } else {
+        Ok(())
+    }
+}
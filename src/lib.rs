@@ -1,6 +1,24 @@
+#![cfg_attr(
+    all(feature = "arm-neon", target_arch = "arm"),
+    feature(stdarch_arm_neon_intrinsics, stdarch_arm_feature_detection)
+)]
+
 pub mod block;
+pub mod builder;
 pub mod decoder;
+pub mod distribution;
 pub mod droplet;
 pub mod encoder;
+mod gf2;
+pub mod header;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+pub mod object;
+pub mod online;
+pub mod precode;
+pub mod prng;
+pub mod raptorq;
 pub mod soliton;
-mod xor;
+pub mod stream;
+mod wire;
+pub mod xor;
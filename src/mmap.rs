@@ -0,0 +1,67 @@
+//! Memory-mapped files as encoder input and decoder output.
+//!
+//! Requires the `mmap` feature. The [`Encoder`](crate::encoder::Encoder)
+//! reads the source blocks straight from a mapped input file, the
+//! [`Decoder`](crate::decoder::Decoder) writes every recovered block
+//! straight into a mapped output file of the size of the message. Only
+//! the pages in use are held in memory, and once decoding has finished
+//! the message is already in the output file.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use fountaincode::{
+//!     decoder::{CatchResult, Decoder, DecoderType},
+//!     encoder::{Encoder, EncoderType},
+//!     mmap,
+//! };
+//!
+//! // Nobody else may modify the files while they are mapped.
+//! let input = unsafe { mmap::map_input("disk.img") }.unwrap();
+//! let output = unsafe { mmap::map_output("copy.img", input.len() as u64) }.unwrap();
+//!
+//! let enc = Encoder::ideal(input, 1024, EncoderType::Systematic);
+//! let mut dec = Decoder::with_storage(output, 1024, DecoderType::Peeling);
+//! for drop in enc {
+//!     if let CatchResult::Finished(..) = dec.catch(drop) {
+//!         break;
+//!     }
+//! }
+//! dec.into_storage().flush().unwrap();
+//! ```
+use std::{
+    fs::{File, OpenOptions},
+    io,
+    path::Path,
+};
+
+pub use memmap2::{Mmap, MmapMut};
+
+/// Maps the file at `path` read-only.
+///
+/// # Safety
+///
+/// The file must not be modified or truncated while it is mapped,
+/// neither by this process nor by others.
+pub unsafe fn map_input<P: AsRef<Path>>(path: P) -> io::Result<Mmap> {
+    let file = File::open(path)?;
+    Mmap::map(&file)
+}
+
+/// Creates the file at `path` with a length of `len` bytes, truncating
+/// it if it exists, and maps it writable.
+///
+/// # Safety
+///
+/// The file must not be modified or truncated by others while it is
+/// mapped.
+pub unsafe fn map_output<P: AsRef<Path>>(path: P, len: u64) -> io::Result<MmapMut> {
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)?;
+    file.set_len(len)?;
+    MmapMut::map_mut(&file)
+}
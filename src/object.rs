@@ -0,0 +1,392 @@
+//! Encoding objects larger than one code block.
+//!
+//! A single LT [`Encoder`] over a whole object lets the number of
+//! blocks k grow with the object, and with it the cost of sampling the
+//! Robust Soliton distribution and the memory of the peeling decoder.
+//! Instead, a [`Partition`] splits the object into Z source blocks of at
+//! most a given number of symbols, like the partitioning of RFC 5053
+//! and RFC 6330: the first ZL source blocks have KL symbols, the
+//! remaining ZS ones KS = KL - 1.
+//!
+//! The [`ObjectEncoder`] encodes every source block with its own LT
+//! encoder and sends their droplets round-robin, tagged with the source
+//! block number as a [`TaggedDroplet`]. The [`ObjectDecoder`] routes
+//! every droplet to the decoder of its source block and reports the
+//! progress of each.
+//!
+//! # Example
+//!
+//! ```
+//! use fountaincode::{
+//!     encoder::EncoderType,
+//!     object::{ObjectDecoder, ObjectEncoder},
+//! };
+//!
+//! let msg: Vec<u8> = (0..100_000).map(|i| i as u8).collect();
+//! let enc = ObjectEncoder::new(msg.clone(), 64, 200, EncoderType::Random).unwrap();
+//! let mut dec = ObjectDecoder::new(msg.len(), 64, 200).unwrap();
+//! assert_eq!(enc.partitioning().cnt_source_blocks(), 8);
+//!
+//! for drop in enc {
+//!     if dec.catch(drop).unwrap() {
+//!         break;
+//!     }
+//! }
+//! assert_eq!(dec.into_data(), msg);
+//! ```
+use crate::{
+    builder::{check_blocksize, ConfigError, DecoderBuilder, EncoderBuilder},
+    decoder::{DecodeError, Decoder, DecoderType, Statistics},
+    encoder::{Encoder, EncoderType},
+    raptorq::partition,
+    soliton::Soliton,
+    stream::{block_seed, TaggedDroplet},
+};
+use std::{convert::TryFrom, ops::Range, sync::Arc};
+
+/// How an object is split into source blocks, shared by encoder and
+/// decoder. The [`stream`](crate::stream) module splits its objects the
+/// same way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Partition {
+    len: u64,
+    blocksize: usize,
+    // KL, KS, ZL and ZS of RFC 6330
+    kl: usize,
+    ks: usize,
+    zl: usize,
+    zs: usize,
+}
+
+impl Partition {
+    /// Splits an object of `len` bytes into as few source blocks of at
+    /// most `max_block_symbols` symbols of `blocksize` bytes as
+    /// possible, all about the same size. An empty object has no source
+    /// blocks.
+    pub fn new(
+        len: u64,
+        blocksize: usize,
+        max_block_symbols: usize,
+    ) -> Result<Partition, ConfigError> {
+        check_blocksize(blocksize)?;
+        if max_block_symbols == 0 {
+            return Err(ConfigError::ZeroBlockSymbols);
+        }
+        let kt = len.div_ceil(blocksize as u64);
+        let z = kt.div_ceil(max_block_symbols as u64);
+        let too_many =
+            || ConfigError::TooManySourceBlocks(usize::try_from(z).unwrap_or(usize::MAX));
+        if z > u32::MAX as u64 + 1 {
+            return Err(too_many());
+        }
+        let (kl, ks, zl, zs) = match z {
+            0 => (0, 0, 0, 0),
+            _ => partition(usize::try_from(kt).map_err(|_| too_many())?, z as usize),
+        };
+        Ok(Partition {
+            len,
+            blocksize,
+            kl,
+            ks,
+            zl,
+            zs,
+        })
+    }
+
+    /// Length of the object in bytes.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Whether the object is empty. Only a [`StreamEncoder`] accepts
+    /// empty objects, it then sends no droplets at all.
+    ///
+    /// [`StreamEncoder`]: crate::stream::StreamEncoder
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn blocksize(&self) -> usize {
+        self.blocksize
+    }
+
+    pub fn cnt_source_blocks(&self) -> usize {
+        self.zl + self.zs
+    }
+
+    /// Number of symbols of source block `sbn`.
+    pub fn block_symbols(&self, sbn: usize) -> usize {
+        if sbn < self.zl {
+            self.kl
+        } else {
+            self.ks
+        }
+    }
+
+    /// The bytes of the object in source block `sbn`. Only the last
+    /// source block may end in a partial symbol.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sbn` is not a source block of the object.
+    pub fn block(&self, sbn: usize) -> Range<u64> {
+        assert!(sbn < self.cnt_source_blocks(), "no source block {}", sbn);
+        let symbols_before = (sbn * self.ks + sbn.min(self.zl)) as u64;
+        let begin = symbols_before * self.blocksize as u64;
+        let end = begin + (self.block_symbols(sbn) * self.blocksize) as u64;
+        begin..end.min(self.len)
+    }
+
+    /// Like [`Partition::block`], for objects in memory.
+    fn block_range(&self, sbn: usize) -> Range<usize> {
+        let block = self.block(sbn);
+        block.start as usize..block.end as usize
+    }
+}
+
+/// A source block of the object, without copying it.
+struct Window<S> {
+    data: Arc<S>,
+    range: Range<usize>,
+}
+
+impl<S> Clone for Window<S> {
+    fn clone(&self) -> Self {
+        Window {
+            data: Arc::clone(&self.data),
+            range: self.range.clone(),
+        }
+    }
+}
+
+impl<S: AsRef<[u8]>> AsRef<[u8]> for Window<S> {
+    fn as_ref(&self) -> &[u8] {
+        &(*self.data).as_ref()[self.range.clone()]
+    }
+}
+
+/// Encoder for objects split into several source blocks.
+///
+/// Droplet n belongs to source block `n % Z`. As an `Iterator` it
+/// yields droplets forever.
+#[derive(Clone)]
+pub struct ObjectEncoder<S = Vec<u8>> {
+    partition: Partition,
+    encoders: Vec<Encoder<Soliton, Window<S>>>,
+    seed: u64,
+    cnt: usize,
+}
+
+impl<S: AsRef<[u8]>> ObjectEncoder<S> {
+    /// Encodes `data` in source blocks of at most `max_block_symbols`
+    /// symbols of `blocksize` bytes, with the defaults of
+    /// [`EncoderBuilder`].
+    pub fn new(
+        data: S,
+        blocksize: usize,
+        max_block_symbols: usize,
+        encodertype: EncoderType,
+    ) -> Result<Self, ConfigError> {
+        let builder = EncoderBuilder::new(data)
+            .blocksize(blocksize)
+            .encoder_type(encodertype);
+        ObjectEncoder::from_builder(builder, max_block_symbols)
+    }
+
+    /// Encodes the data of `builder` in source blocks of at most
+    /// `max_block_symbols` symbols, each with the settings of `builder`.
+    /// The seed of `builder`, or a random one, becomes the master seed
+    /// of the object, see [`ObjectEncoder::with_seed`].
+    pub fn from_builder(
+        builder: EncoderBuilder<S>,
+        max_block_symbols: usize,
+    ) -> Result<Self, ConfigError> {
+        let (data, settings) = builder.take_data();
+        let len = data.as_ref().len();
+        if len == 0 {
+            return Err(ConfigError::EmptyMessage);
+        }
+        let partition = Partition::new(len as u64, settings.blocksize, max_block_symbols)?;
+        let seed = settings.seed.unwrap_or_else(rand::random);
+        let data = Arc::new(data);
+        let encoders = (0..partition.cnt_source_blocks())
+            .map(|sbn| {
+                let window = Window {
+                    data: Arc::clone(&data),
+                    range: partition.block_range(sbn),
+                };
+                settings
+                    .clone()
+                    .data(window)
+                    .seed(block_seed(seed, sbn))
+                    .build()
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(ObjectEncoder {
+            partition,
+            encoders,
+            seed,
+            cnt: 0,
+        })
+    }
+
+    /// Derives the droplets of all source blocks from the master
+    /// `seed`, so that they can be reproduced. Source block `sbn` is
+    /// encoded like the one of a [`StreamEncoder`] with the same master
+    /// seed.
+    ///
+    /// [`StreamEncoder`]: crate::stream::StreamEncoder
+    pub fn with_seed(self, seed: u64) -> Self {
+        ObjectEncoder {
+            encoders: self
+                .encoders
+                .into_iter()
+                .enumerate()
+                .map(|(sbn, enc)| enc.with_seed(block_seed(seed, sbn)))
+                .collect(),
+            seed,
+            ..self
+        }
+    }
+
+    /// The master seed the encoders of all source blocks are derived
+    /// from.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn partitioning(&self) -> &Partition {
+        &self.partition
+    }
+
+    /// Produces the next droplet.
+    pub fn drop(&mut self) -> TaggedDroplet {
+        let drop = self.drop_at(self.cnt);
+        self.cnt += 1;
+        drop
+    }
+
+    /// Regenerates droplet `n` of the object.
+    pub fn drop_at(&self, n: usize) -> TaggedDroplet {
+        let z = self.encoders.len();
+        let sbn = n % z;
+        TaggedDroplet::new(sbn as u32, self.encoders[sbn].drop_at(n / z))
+    }
+}
+
+impl<S: AsRef<[u8]>> Iterator for ObjectEncoder<S> {
+    type Item = TaggedDroplet;
+    fn next(&mut self) -> Option<TaggedDroplet> {
+        Some(self.drop())
+    }
+}
+
+/// Decoder for droplets of an [`ObjectEncoder`].
+pub struct ObjectDecoder {
+    partition: Partition,
+    decoders: Vec<Decoder>,
+    cnt_finished: usize,
+}
+
+impl ObjectDecoder {
+    /// Decodes an object of `len` bytes. The parameters have to match
+    /// the encoder's.
+    pub fn new(
+        len: usize,
+        blocksize: usize,
+        max_block_symbols: usize,
+    ) -> Result<Self, ConfigError> {
+        ObjectDecoder::with_type(len, blocksize, max_block_symbols, DecoderType::Peeling)
+    }
+
+    pub fn with_type(
+        len: usize,
+        blocksize: usize,
+        max_block_symbols: usize,
+        decodertype: DecoderType,
+    ) -> Result<Self, ConfigError> {
+        let builder = DecoderBuilder::new(len)
+            .blocksize(blocksize)
+            .decoder_type(decodertype);
+        ObjectDecoder::from_builder(builder, max_block_symbols)
+    }
+
+    /// Decodes the object `builder` describes, in source blocks of at
+    /// most `max_block_symbols` symbols, each with the settings of
+    /// `builder`.
+    pub fn from_builder(
+        builder: DecoderBuilder,
+        max_block_symbols: usize,
+    ) -> Result<Self, ConfigError> {
+        if builder.len == 0 {
+            return Err(ConfigError::EmptyMessage);
+        }
+        let partition = Partition::new(builder.len as u64, builder.blocksize, max_block_symbols)?;
+        let decoders = (0..partition.cnt_source_blocks())
+            .map(|sbn| {
+                let len = partition.block_range(sbn).len();
+                builder.clone().with_len(len).build()
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(ObjectDecoder {
+            partition,
+            decoders,
+            cnt_finished: 0,
+        })
+    }
+
+    pub fn partitioning(&self) -> &Partition {
+        &self.partition
+    }
+
+    /// Catches a droplet. Returns true once every source block has been
+    /// decoded.
+    pub fn catch(&mut self, drop: TaggedDroplet) -> Result<bool, DecodeError> {
+        let cnt_source_blocks = self.decoders.len();
+        let dec =
+            self.decoders
+                .get_mut(drop.sbn as usize)
+                .ok_or(DecodeError::UnknownSourceBlock {
+                    sbn: drop.sbn as usize,
+                    cnt_source_blocks,
+                })?;
+        let was_finished = dec.is_finished();
+        dec.try_catch(drop.droplet)?;
+        if !was_finished && dec.is_finished() {
+            self.cnt_finished += 1;
+        }
+        Ok(self.is_finished())
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.cnt_finished == self.decoders.len()
+    }
+
+    /// Number of source blocks decoded so far.
+    pub fn cnt_finished_blocks(&self) -> usize {
+        self.cnt_finished
+    }
+
+    pub fn is_block_finished(&self, sbn: usize) -> bool {
+        self.decoders[sbn].is_finished()
+    }
+
+    /// The progress of source block `sbn`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sbn` is not a source block of the object.
+    pub fn block_statistics(&self, sbn: usize) -> Statistics {
+        self.decoders[sbn].statistics()
+    }
+
+    /// Returns the object. Unless decoding has finished, the unknown
+    /// blocks are zeroed.
+    pub fn into_data(self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(self.partition.len() as usize);
+        for dec in self.decoders {
+            data.extend_from_slice(&dec.into_data());
+        }
+        data
+    }
+}
@@ -0,0 +1,216 @@
+//! Online codes as described by Petar Maymounkov in
+//! [Online Codes](http://pdos.csail.mit.edu/~petar/papers/maymounkov-online.pdf).
+//!
+//! An outer code first appends `ceil(0.55 * q * epsilon * n)`
+//! auxiliary blocks to the `n` message blocks, every message block is
+//! xored into `q` of them. The inner code then sends check blocks over
+//! these composite blocks, with degrees drawn from the
+//! [`OnlineDistribution`]. The decoder peels the check blocks just like
+//! the LT [`Decoder`](crate::decoder::Decoder), the outer code lets it
+//! recover the few message blocks the inner code missed.
+//!
+//! # Example
+//!
+//! ```
+//! use fountaincode::{
+//!     decoder::CatchResult,
+//!     online::{Decoder, Encoder, OnlineParams},
+//! };
+//!
+//! let msg: Vec<u8> = (0..255).collect();
+//! let params = OnlineParams::default();
+//!
+//! let mut enc = Encoder::new(msg.clone(), 16, params);
+//! let mut dec = Decoder::new(msg.len(), 16, params);
+//!
+//! loop {
+//!     if let CatchResult::Finished(data, stats) = dec.catch(enc.drop()) {
+//!         assert_eq!(data, msg);
+//!         println!("Finished, stats: {:?}", stats);
+//!         break;
+//!     }
+//! }
+//! ```
+use crate::{
+    decoder::{self, CatchResult, DecodeError, DecoderType},
+    droplet::{DropType, Droplet},
+    header::EdgeGenerator,
+    precode::Precode,
+    xor::xor_many,
+};
+use rand::{distributions::Distribution, rngs::StdRng, Rng, SeedableRng};
+use std::cmp;
+
+/// The parameters of an Online code.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OnlineParams {
+    /// Decoding succeeds after `(1 + epsilon) * n` check blocks with
+    /// high probability.
+    pub epsilon: f64,
+    /// Number of auxiliary blocks every message block is xored into.
+    pub q: usize,
+}
+
+impl Default for OnlineParams {
+    /// The parameters suggested in the paper, epsilon = 0.01 and q = 3.
+    fn default() -> Self {
+        OnlineParams {
+            epsilon: 0.01,
+            q: 3,
+        }
+    }
+}
+
+impl OnlineParams {
+    /// Number of auxiliary blocks for `n` message blocks.
+    pub fn cnt_aux_blocks(&self, n: usize) -> usize {
+        ((0.55 * self.q as f64 * self.epsilon * n as f64).ceil() as usize).max(1)
+    }
+}
+
+/// The degree distribution of the inner code.
+///
+/// With `F = ceil(ln(epsilon^2 / 4) / ln(1 - epsilon / 2))`, degree 1 has
+/// probability `p1 = 1 - (1 + 1/F) / (1 + epsilon)` and degree `2 <= i <= F`
+/// has probability `(1 - p1) F / ((F - 1) i (i - 1))`.
+#[derive(Debug, Clone)]
+pub struct OnlineDistribution {
+    cdf: Vec<f64>,
+}
+
+impl OnlineDistribution {
+    pub fn new(epsilon: f64) -> Self {
+        let f = ((epsilon * epsilon / 4.0).ln() / (1.0 - epsilon / 2.0).ln()).ceil();
+        let f = f.max(2.0);
+        let p1 = 1.0 - (1.0 + 1.0 / f) / (1.0 + epsilon);
+
+        let mut cdf = Vec::with_capacity(f as usize);
+        let mut sum = p1;
+        cdf.push(sum);
+        for i in 2..=f as usize {
+            let i = i as f64;
+            sum += (1.0 - p1) * f / ((f - 1.0) * i * (i - 1.0));
+            cdf.push(sum);
+        }
+        OnlineDistribution { cdf }
+    }
+
+    /// The largest degree F.
+    pub fn max_degree(&self) -> usize {
+        self.cdf.len()
+    }
+
+    /// Probability of drawing `degree`.
+    pub fn pmf(&self, degree: usize) -> f64 {
+        match degree {
+            0 => 0.0,
+            1 => self.cdf[0],
+            d if d <= self.cdf.len() => self.cdf[d - 1] - self.cdf[d - 2],
+            _ => 0.0,
+        }
+    }
+}
+
+impl Distribution<usize> for OnlineDistribution {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        // The cdf adds up to 1 only up to rounding.
+        let u = rng.gen::<f64>() * self.cdf[self.cdf.len() - 1];
+        let idx = self.cdf.partition_point(|&p| p <= u);
+        cmp::min(idx, self.cdf.len() - 1) + 1
+    }
+}
+
+/// The outer code is a precode whose parity blocks are the auxiliary
+/// blocks.
+fn outer_code(n: usize, params: OnlineParams) -> Precode {
+    Precode::new(n, params.cnt_aux_blocks(n), params.q)
+}
+
+/// Encoder for Online codes.
+///
+/// Produces `Seeded` droplets over the message and auxiliary blocks.
+#[derive(Clone)]
+pub struct Encoder {
+    data: Vec<u8>,
+    blocksize: usize,
+    rng: StdRng,
+    cnt_blocks: usize,
+    sol: OnlineDistribution,
+    pub cnt: usize,
+}
+
+impl Encoder {
+    pub fn new(data: Vec<u8>, blocksize: usize, params: OnlineParams) -> Self {
+        let outer = outer_code(data.len().div_ceil(blocksize), params);
+        let composite = outer.encode(data, blocksize);
+
+        Encoder {
+            data: composite,
+            blocksize,
+            rng: StdRng::from_entropy(),
+            cnt_blocks: outer.cnt_blocks(),
+            sol: OnlineDistribution::new(params.epsilon),
+            cnt: 0,
+        }
+    }
+
+    pub fn drop(&mut self) -> Droplet {
+        let mut r = vec![0; self.blocksize];
+        // F may exceed the number of blocks of a short message
+        let degree = cmp::min(self.sol.sample(&mut self.rng), self.cnt_blocks);
+        let seed = self.rng.gen::<u64>();
+        let blocks: Vec<&[u8]> = EdgeGenerator::default()
+            .sample(seed, self.cnt_blocks, degree)
+            .into_iter()
+            .map(|k| &self.data[k * self.blocksize..(k + 1) * self.blocksize])
+            .collect();
+        xor_many(&mut r, &blocks);
+        self.cnt += 1;
+        Droplet::new(DropType::Seeded(seed, degree), r)
+    }
+}
+
+impl Iterator for Encoder {
+    type Item = Droplet;
+    fn next(&mut self) -> Option<Droplet> {
+        Some(self.drop())
+    }
+}
+
+/// Decoder for Online codes.
+///
+/// The auxiliary blocks are decoded alongside the message blocks, the
+/// outer code enters the decoder as droplets that add up to zero.
+pub struct Decoder {
+    decoder: decoder::Decoder,
+}
+
+impl Decoder {
+    pub fn new(len: usize, blocksize: usize, params: OnlineParams) -> Decoder {
+        Decoder::with_type(len, blocksize, params, DecoderType::Peeling)
+    }
+
+    pub fn with_type(
+        len: usize,
+        blocksize: usize,
+        params: OnlineParams,
+        decodertype: DecoderType,
+    ) -> Decoder {
+        let outer = outer_code(len.div_ceil(blocksize), params);
+        Decoder {
+            decoder: outer.decoder(len, blocksize, decodertype),
+        }
+    }
+
+    /// Catches a Droplet
+    /// When all message blocks are known, the bytes are returned
+    pub fn catch(&mut self, drop: Droplet) -> CatchResult<'_> {
+        self.decoder.catch(drop)
+    }
+
+    /// Like [`Decoder::catch`], but rejects malformed droplets instead
+    /// of panicking.
+    pub fn try_catch(&mut self, drop: Droplet) -> Result<CatchResult<'_>, DecodeError> {
+        self.decoder.try_catch(drop)
+    }
+}
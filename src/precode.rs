@@ -0,0 +1,146 @@
+//! Systematic LDPC precode for LT codes, as used by Raptor codes.
+//!
+//! Plain LT codes need a large overhead to recover the last few blocks,
+//! every block has to be covered by at least one droplet. A precode
+//! appends parity blocks to the k source blocks first, each source
+//! block is xored into `degree` of them. The LT [`Encoder`] then runs
+//! over source and parity blocks alike. The decoder treats every parity
+//! block as a constraint, so it can finish while LT peeling still
+//! misses a few source blocks. The constraints pay off most with
+//! [`DecoderType::Inactivation`], which solves them together with the
+//! droplets once peeling stalls.
+//!
+//! # Example
+//!
+//! ```
+//! use fountaincode::{
+//!     decoder::{CatchResult, DecoderType},
+//!     encoder::{Encoder, EncoderType},
+//!     precode::Precode,
+//! };
+//!
+//! let msg: Vec<u8> = (0..255).collect();
+//! let blocksize = 8;
+//!
+//! let precode = Precode::new(msg.len().div_ceil(blocksize), 3, 3);
+//! let intermediate = precode.encode(msg.clone(), blocksize);
+//! let mut enc = Encoder::ideal(intermediate, blocksize, EncoderType::Random);
+//! let mut dec = precode.decoder(msg.len(), blocksize, DecoderType::Peeling);
+//!
+//! loop {
+//!     if let CatchResult::Finished(data, _) = dec.catch(enc.drop()) {
+//!         assert_eq!(data, msg);
+//!         break;
+//!     }
+//! }
+//! ```
+//!
+//! [`Encoder`]: crate::encoder::Encoder
+use crate::{
+    decoder::{Decoder, DecoderType},
+    prng::SplitMix64,
+    xor::xor_bytes,
+};
+use std::cmp;
+
+// Seeds the parity graph, which both sides derive from the parameters.
+const GRAPH_SEED: u64 = 0x0070_7265_636f_6465;
+
+/// A systematic LDPC precode, the source blocks followed by parity
+/// blocks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Precode {
+    cnt_source: usize,
+    // For every parity block, the source blocks xored into it.
+    parity: Vec<Vec<usize>>,
+}
+
+impl Precode {
+    /// Creates a precode for `cnt_source` source blocks with `cnt_parity`
+    /// parity blocks. Every source block is xored into `degree`
+    /// distinct parity blocks, at most `cnt_parity`.
+    ///
+    /// The graph is derived from the parameters alone, so encoder and
+    /// decoder only need to agree on them.
+    pub fn new(cnt_source: usize, cnt_parity: usize, degree: usize) -> Self {
+        let degree = cmp::min(degree, cnt_parity);
+        let seed = GRAPH_SEED ^ (cnt_source as u64) ^ ((cnt_parity as u64) << 32);
+        let mut rng = SplitMix64::new(seed ^ ((degree as u64) << 56));
+        let mut parity = vec![Vec::new(); cnt_parity];
+        let mut slots: Vec<usize> = (0..cnt_parity).collect();
+        for src_idx in 0..cnt_source {
+            // A partial Fisher-Yates shuffle picks distinct parity blocks.
+            for i in 0..degree {
+                let j = i + rng.below(cnt_parity - i);
+                slots.swap(i, j);
+                parity[slots[i]].push(src_idx);
+            }
+        }
+        Precode { cnt_source, parity }
+    }
+
+    pub fn cnt_source_blocks(&self) -> usize {
+        self.cnt_source
+    }
+
+    pub fn cnt_parity_blocks(&self) -> usize {
+        self.parity.len()
+    }
+
+    /// Number of intermediate blocks, source and parity.
+    pub fn cnt_blocks(&self) -> usize {
+        self.cnt_source + self.parity.len()
+    }
+
+    /// Returns the intermediate blocks to feed into the LT `Encoder`:
+    /// `data`, zero padded to full blocks, followed by the parity
+    /// blocks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `data` does not consist of exactly the source blocks
+    /// of this precode.
+    pub fn encode(&self, data: Vec<u8>, blocksize: usize) -> Vec<u8> {
+        assert_eq!(data.len().div_ceil(blocksize), self.cnt_source);
+        let mut intermediate = data;
+        intermediate.resize(self.cnt_blocks() * blocksize, 0);
+        for (par_idx, src_blocks) in self.parity.iter().enumerate() {
+            let (src, par) = intermediate.split_at_mut((self.cnt_source + par_idx) * blocksize);
+            for &src_idx in src_blocks {
+                let begin = src_idx * blocksize;
+                xor_bytes(&mut par[..blocksize], &src[begin..begin + blocksize]);
+            }
+        }
+        intermediate
+    }
+
+    /// Creates a decoder for droplets of the intermediate blocks, which
+    /// finishes with the `len` bytes of the source blocks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` does not match the source blocks of this precode.
+    pub fn decoder(&self, len: usize, blocksize: usize, decodertype: DecoderType) -> Decoder {
+        assert_eq!(len.div_ceil(blocksize), self.cnt_source);
+        Decoder::with_constraints(
+            vec![0; len],
+            blocksize,
+            self.parity.len(),
+            self.constraints(),
+            decodertype,
+        )
+    }
+
+    /// Every parity block plus its source blocks adds up to zero.
+    fn constraints(&self) -> Vec<Vec<usize>> {
+        self.parity
+            .iter()
+            .enumerate()
+            .map(|(par_idx, src_blocks)| {
+                let mut edges = src_blocks.clone();
+                edges.push(self.cnt_source + par_idx);
+                edges
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,96 @@
+//! A small, frozen pseudo random number generator for droplet edges.
+//!
+//! Encoder and decoder expand the seed of every droplet into the same
+//! block indices, so the generator must never change. The algorithms of
+//! `rand` are explicitly not stable across releases, the ones here are:
+//!
+//! * [SplitMix64](https://prng.di.unimi.it/splitmix64.c): the state
+//!   starts at the seed, every step adds `0x9e3779b97f4a7c15` and
+//!   outputs the mixed state.
+//! * An index below `n` is the upper 64 bits of the 128 bit product of
+//!   the next output and `n`. This is slightly biased for huge `n`, but
+//!   needs no rejection loop and is simple to port.
+//!
+//! [`SplitMix64`] also implements `RngCore` and `SeedableRng`, so that
+//! the [`Encoder`](crate::encoder::Encoder) can derive its droplets from
+//! it. `seed_from_u64` uses the seed as the state as is.
+//!
+//! # Example
+//!
+//! ```
+//! use fountaincode::prng::SplitMix64;
+//!
+//! let mut rng = SplitMix64::new(0);
+//! assert_eq!(rng.next_u64(), 0xe220_a839_7b1d_cdaf);
+//! assert_eq!(SplitMix64::new(42).below(1000), 741);
+//! ```
+use rand::{Error, RngCore, SeedableRng};
+
+/// The SplitMix64 generator.
+#[derive(Debug, Clone)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        SplitMix64 { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns an index in `0..n`.
+    pub fn below(&mut self, n: usize) -> usize {
+        ((self.next_u64() as u128 * n as u128) >> 64) as usize
+    }
+}
+
+/// Derives the `n`th seed from `seed`. Both `n` and the result go
+/// through a full SplitMix64 step, so that neither neighbouring `n` nor
+/// seeds a step apart share a stream.
+pub(crate) fn derive_seed(seed: u64, n: u64) -> u64 {
+    SplitMix64::new(seed ^ SplitMix64::new(n).next_u64()).next_u64()
+}
+
+impl RngCore for SplitMix64 {
+    /// The upper half of the next output.
+    fn next_u32(&mut self) -> u32 {
+        (SplitMix64::next_u64(self) >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        SplitMix64::next_u64(self)
+    }
+
+    /// Fills `dest` with the little-endian bytes of the next outputs.
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = SplitMix64::next_u64(self).to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl SeedableRng for SplitMix64 {
+    type Seed = [u8; 8];
+
+    /// Takes the seed as a little-endian state.
+    fn from_seed(seed: [u8; 8]) -> Self {
+        SplitMix64::new(u64::from_le_bytes(seed))
+    }
+
+    fn seed_from_u64(seed: u64) -> Self {
+        SplitMix64::new(seed)
+    }
+}
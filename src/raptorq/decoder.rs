@@ -0,0 +1,202 @@
+use super::{
+    encoder::encode_symbol,
+    params::BlockParams,
+    solver::{self, BinaryRow},
+    BlockLayout, EncodingPacket, ObjectTransmissionInformation,
+};
+use std::{cmp, collections::BTreeMap};
+
+/// RaptorQ decoder for a whole object.
+///
+/// Packets may arrive in any order and for any source block.
+/// Packets for unknown source blocks or with a wrong symbol size are
+/// ignored.
+#[derive(Debug, Clone)]
+pub struct Decoder {
+    oti: ObjectTransmissionInformation,
+    blocks: Vec<(usize, SourceBlockDecoder)>,
+    data: Vec<u8>,
+    cnt_decoded: usize,
+}
+
+impl Decoder {
+    pub fn new(oti: ObjectTransmissionInformation) -> Self {
+        let blocks = oti
+            .layouts()
+            .into_iter()
+            .enumerate()
+            .map(|(sbn, (begin, layout))| (begin, SourceBlockDecoder::new(sbn as u8, layout)))
+            .collect();
+        Decoder {
+            oti,
+            blocks,
+            data: vec![0; oti.transfer_length() as usize],
+            cnt_decoded: 0,
+        }
+    }
+
+    pub fn oti(&self) -> ObjectTransmissionInformation {
+        self.oti
+    }
+
+    /// Catches a packet. Once every source block is decoded, the object
+    /// is returned, without copying it.
+    pub fn catch(&mut self, packet: EncodingPacket) -> Option<&[u8]> {
+        let sbn = packet.payload_id.sbn() as usize;
+        if let Some((begin, block)) = self.blocks.get_mut(sbn) {
+            let was_decoded = block.is_decoded();
+            block.catch(packet);
+            if !was_decoded && block.is_decoded() {
+                let end = (*begin + block.block_len()).min(self.data.len());
+                block.write_block(&mut self.data[*begin..end]);
+                self.cnt_decoded += 1;
+            }
+        }
+        if self.is_decoded() {
+            Some(&self.data)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_decoded(&self) -> bool {
+        self.cnt_decoded == self.blocks.len()
+    }
+
+    /// Returns the object. Unless decoding has finished, the source
+    /// blocks not decoded yet are zeroed.
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+
+    /// The decoders of the individual source blocks, to follow the
+    /// progress per block.
+    pub fn blocks(&self) -> impl Iterator<Item = &SourceBlockDecoder> {
+        self.blocks.iter().map(|(_, block)| block)
+    }
+}
+
+/// RaptorQ decoder for a single source block.
+#[derive(Debug, Clone)]
+pub struct SourceBlockDecoder {
+    sbn: u8,
+    params: BlockParams,
+    layout: BlockLayout,
+    received: BTreeMap<u32, Vec<u8>>,
+    source: Option<Vec<Vec<u8>>>,
+    // Symbols to receive before solving again, and the gap after that
+    next_solve: usize,
+    solve_gap: usize,
+}
+
+impl SourceBlockDecoder {
+    fn new(sbn: u8, layout: BlockLayout) -> Self {
+        let k = layout.k;
+        SourceBlockDecoder {
+            sbn,
+            params: BlockParams::new(k),
+            layout,
+            received: BTreeMap::new(),
+            source: None,
+            next_solve: k,
+            solve_gap: 1,
+        }
+    }
+
+    pub fn sbn(&self) -> u8 {
+        self.sbn
+    }
+
+    /// Number of source symbols K in this block.
+    pub fn cnt_source_symbols(&self) -> usize {
+        self.params.k
+    }
+
+    /// Number of distinct encoding symbols received so far.
+    pub fn cnt_received(&self) -> usize {
+        self.received.len()
+    }
+
+    pub fn is_decoded(&self) -> bool {
+        self.source.is_some()
+    }
+
+    fn block_len(&self) -> usize {
+        self.layout.k * self.layout.symbol_size()
+    }
+
+    fn write_block(&self, out: &mut [u8]) {
+        if let Some(source) = &self.source {
+            self.layout.write_symbols(source, out);
+        }
+    }
+
+    /// Catches a packet of this block. Returns true once the block is
+    /// decoded.
+    pub fn catch(&mut self, packet: EncodingPacket) -> bool {
+        if self.is_decoded()
+            || packet.payload_id.sbn() != self.sbn
+            || packet.data.len() != self.layout.symbol_size()
+        {
+            return self.is_decoded();
+        }
+        self.received.insert(packet.payload_id.esi(), packet.data);
+        let k = self.params.k;
+        if self.received.range(..k as u32).count() == k {
+            self.source = Some(self.received.values().take(k).cloned().collect());
+        } else if self.received.len() >= self.next_solve {
+            self.source = self.try_decode();
+            // Solving costs O(K^3), so do not repeat it for every
+            // symbol. RaptorQ almost always succeeds within two extra
+            // symbols, hence the gap starts at one and doubles up to
+            // K / 16, like the elimination schedule of the LT decoder.
+            self.next_solve = self.received.len() + self.solve_gap;
+            self.solve_gap = cmp::min(2 * self.solve_gap, cmp::max(1, k / 16));
+        }
+        self.is_decoded()
+    }
+
+    fn try_decode(&self) -> Option<Vec<Vec<u8>>> {
+        let k = self.params.k;
+
+        let params = &self.params;
+        let symbol_size = self.layout.symbol_size();
+        let mut rows: Vec<BinaryRow> = params
+            .ldpc_rows()
+            .into_iter()
+            .map(|cols| BinaryRow {
+                cols,
+                data: vec![0; symbol_size],
+            })
+            .collect();
+        // The padding symbols between K and K' are known to be zero.
+        for isi in k..params.k_prime {
+            rows.push(BinaryRow {
+                cols: params.isi_indices(isi as u32),
+                data: vec![0; symbol_size],
+            });
+        }
+        for (&esi, data) in &self.received {
+            let isi = if (esi as usize) < k {
+                esi
+            } else {
+                esi + (params.k_prime - k) as u32
+            };
+            rows.push(BinaryRow {
+                cols: params.isi_indices(isi),
+                data: data.clone(),
+            });
+        }
+
+        let intermediate =
+            solver::solve(params.l, params.w, symbol_size, rows, &params.hdpc_rows())?;
+        Some(
+            (0..k as u32)
+                .map(|esi| match self.received.get(&esi) {
+                    Some(data) => data.clone(),
+                    None => encode_symbol(params, &intermediate, esi),
+                })
+                .collect(),
+        )
+    }
+}
@@ -0,0 +1,185 @@
+use super::{
+    params::BlockParams,
+    solver::{self, BinaryRow},
+    BlockLayout, EncodingPacket, ObjectTransmissionInformation, PayloadId, RaptorQError, MAX_ESI,
+};
+use crate::xor::xor_bytes;
+
+/// Systematic RaptorQ encoder for a whole object.
+///
+/// As an `Iterator` it yields packets round-robin over the source
+/// blocks: first ESI 0 of every block, then ESI 1, and so on. The
+/// first K packets of a block are its source symbols, everything after
+/// that is repair.
+#[derive(Debug, Clone)]
+pub struct Encoder {
+    oti: ObjectTransmissionInformation,
+    blocks: Vec<SourceBlockEncoder>,
+    cnt: usize,
+}
+
+impl Encoder {
+    /// Encodes `data` with explicitly chosen parameters. The length of
+    /// `data` must match the transfer length of `oti`.
+    pub fn new(data: &[u8], oti: ObjectTransmissionInformation) -> Result<Self, RaptorQError> {
+        if data.len() as u64 != oti.transfer_length() {
+            return Err(RaptorQError::LengthMismatch {
+                expected: oti.transfer_length(),
+                actual: data.len(),
+            });
+        }
+        let blocks = oti
+            .layouts()
+            .into_iter()
+            .enumerate()
+            .map(|(sbn, (begin, layout))| {
+                let end = (begin + layout.k * oti.symbol_size() as usize).min(data.len());
+                SourceBlockEncoder::new(sbn as u8, &data[begin..end], layout)
+            })
+            .collect();
+        Ok(Encoder {
+            oti,
+            blocks,
+            cnt: 0,
+        })
+    }
+
+    /// Encodes `data` with the parameters of
+    /// [`ObjectTransmissionInformation::with_defaults`].
+    pub fn with_defaults(data: &[u8], symbol_size: u16) -> Result<Self, RaptorQError> {
+        let oti = ObjectTransmissionInformation::with_defaults(data.len() as u64, symbol_size)?;
+        Self::new(data, oti)
+    }
+
+    /// The parameters the receiver needs to build a `Decoder`.
+    pub fn oti(&self) -> ObjectTransmissionInformation {
+        self.oti
+    }
+
+    /// The encoders of the individual source blocks.
+    pub fn blocks(&self) -> &[SourceBlockEncoder] {
+        &self.blocks
+    }
+
+    /// Produces the packet with the given payload ID, or `None` if the
+    /// source block does not exist.
+    pub fn packet(&self, payload_id: PayloadId) -> Option<EncodingPacket> {
+        self.blocks
+            .get(payload_id.sbn() as usize)
+            .map(|block| block.packet(payload_id.esi()))
+    }
+}
+
+impl Iterator for Encoder {
+    type Item = EncodingPacket;
+    fn next(&mut self) -> Option<EncodingPacket> {
+        let sbn = self.cnt % self.blocks.len();
+        let esi = self.cnt / self.blocks.len();
+        if esi > MAX_ESI as usize {
+            return None;
+        }
+        self.cnt += 1;
+        Some(self.blocks[sbn].packet(esi as u32))
+    }
+}
+
+/// Systematic RaptorQ encoder for a single source block.
+#[derive(Debug, Clone)]
+pub struct SourceBlockEncoder {
+    sbn: u8,
+    params: BlockParams,
+    source: Vec<Vec<u8>>,
+    intermediate: Vec<Vec<u8>>,
+}
+
+impl SourceBlockEncoder {
+    fn new(sbn: u8, block: &[u8], layout: BlockLayout) -> Self {
+        let params = BlockParams::new(layout.k);
+        let source = layout.to_symbols(block);
+        let intermediate = intermediate_symbols(&params, &source, layout.symbol_size());
+        SourceBlockEncoder {
+            sbn,
+            params,
+            source,
+            intermediate,
+        }
+    }
+
+    pub fn sbn(&self) -> u8 {
+        self.sbn
+    }
+
+    /// Number of source symbols K in this block.
+    pub fn cnt_source_symbols(&self) -> usize {
+        self.params.k
+    }
+
+    /// Produces the encoding symbol `esi`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `esi` does not fit into 24 bits.
+    pub fn packet(&self, esi: u32) -> EncodingPacket {
+        let payload_id = PayloadId::new(self.sbn, esi);
+        let k = self.params.k;
+        if (esi as usize) < k {
+            return EncodingPacket::new(payload_id, self.source[esi as usize].clone());
+        }
+        let isi = esi + (self.params.k_prime - k) as u32;
+        EncodingPacket::new(
+            payload_id,
+            encode_symbol(&self.params, &self.intermediate, isi),
+        )
+    }
+
+    /// All K source packets of this block.
+    pub fn source_packets(&self) -> Vec<EncodingPacket> {
+        (0..self.params.k as u32)
+            .map(|esi| self.packet(esi))
+            .collect()
+    }
+
+    /// `count` repair packets, starting with the repair symbol
+    /// `first_repair`, which has the ESI K + `first_repair`.
+    pub fn repair_packets(&self, first_repair: u32, count: u32) -> Vec<EncodingPacket> {
+        let first = self.params.k as u32 + first_repair;
+        (first..first + count).map(|esi| self.packet(esi)).collect()
+    }
+}
+
+/// Solves for the intermediate symbols of a block with the K source
+/// symbols `source`, section 5.3.3.4.
+fn intermediate_symbols(
+    params: &BlockParams,
+    source: &[Vec<u8>],
+    symbol_size: usize,
+) -> Vec<Vec<u8>> {
+    let mut rows: Vec<BinaryRow> = params
+        .ldpc_rows()
+        .into_iter()
+        .map(|cols| BinaryRow {
+            cols,
+            data: vec![0; symbol_size],
+        })
+        .collect();
+    for isi in 0..params.k_prime {
+        rows.push(BinaryRow {
+            cols: params.isi_indices(isi as u32),
+            data: source
+                .get(isi)
+                .cloned()
+                .unwrap_or_else(|| vec![0; symbol_size]),
+        });
+    }
+    solver::solve(params.l, params.w, symbol_size, rows, &params.hdpc_rows())
+        .expect("the RaptorQ constraint matrix is invertible for every K'")
+}
+
+/// Enc[K', C, Tuple[K', isi]] of section 5.3.5.3
+pub(super) fn encode_symbol(params: &BlockParams, intermediate: &[Vec<u8>], isi: u32) -> Vec<u8> {
+    let mut symbol = vec![0; intermediate[0].len()];
+    for idx in params.isi_indices(isi) {
+        xor_bytes(&mut symbol, &intermediate[idx]);
+    }
+    symbol
+}
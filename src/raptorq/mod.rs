@@ -0,0 +1,430 @@
+//! RaptorQ forward error correction as specified in
+//! [RFC 6330](https://tools.ietf.org/html/rfc6330).
+//!
+//! Unlike the LT [`Encoder`](crate::encoder::Encoder), RaptorQ is
+//! standardized: packets produced here can be decoded by any other
+//! compliant implementation and vice versa, given the same
+//! [`ObjectTransmissionInformation`].
+//!
+//! The object is partitioned into source blocks and sub-blocks as in
+//! section 4.4.1.2. Every source block is encoded systematically, so
+//! the first K encoding symbols are the source symbols themselves,
+//! followed by an unbounded number of repair symbols. Each
+//! [`EncodingPacket`] carries one encoding symbol together with its
+//! [`PayloadId`].
+//!
+//! # Example
+//!
+//! ```
+//! use fountaincode::raptorq::{Decoder, Encoder, EncodingPacket};
+//!
+//! let msg: Vec<u8> = (0..10_000).map(|i| i as u8).collect();
+//! let enc = Encoder::with_defaults(&msg, 512).unwrap();
+//! let mut dec = Decoder::new(enc.oti());
+//!
+//! // Skip every third packet, as if they got lost on the way.
+//! for (i, packet) in enc.enumerate() {
+//!     if i % 3 == 0 {
+//!         continue;
+//!     }
+//!     let packet = EncodingPacket::from_bytes(&packet.to_bytes()).unwrap();
+//!     if let Some(data) = dec.catch(packet) {
+//!         assert_eq!(data, msg);
+//!         break;
+//!     }
+//! }
+//! ```
+mod decoder;
+mod encoder;
+mod octet;
+mod params;
+mod solver;
+mod tables;
+
+pub use self::decoder::{Decoder, SourceBlockDecoder};
+pub use self::encoder::{Encoder, SourceBlockEncoder};
+pub use self::params::MAX_SOURCE_SYMBOLS;
+
+use crate::wire::read_u32;
+use std::{error, fmt};
+
+/// Largest transfer length F supported by RFC 6330.
+pub const MAX_TRANSFER_LENGTH: u64 = 946_270_874_880;
+/// Largest encoding symbol id, ESIs are 24 bit.
+pub const MAX_ESI: u32 = (1 << 24) - 1;
+
+// Source blocks of the default parameters are at most this many bytes.
+const DEFAULT_MAX_BLOCK_BYTES: usize = 4 << 20;
+
+/// The object transmission information (OTI) of section 3.3: the
+/// parameters sender and receiver need to agree on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ObjectTransmissionInformation {
+    transfer_length: u64,
+    symbol_size: u16,
+    source_blocks: u8,
+    sub_blocks: u16,
+    alignment: u8,
+}
+
+impl ObjectTransmissionInformation {
+    /// Length of the serialized OTI, common and scheme-specific part.
+    pub const ENCODED_LEN: usize = 12;
+
+    /// Validates explicitly chosen parameters: transfer length F, symbol
+    /// size T, number of source blocks Z, number of sub-blocks N and
+    /// symbol alignment Al.
+    pub fn new(
+        transfer_length: u64,
+        symbol_size: u16,
+        source_blocks: u8,
+        sub_blocks: u16,
+        alignment: u8,
+    ) -> Result<Self, RaptorQError> {
+        if transfer_length == 0 || transfer_length > MAX_TRANSFER_LENGTH {
+            return Err(RaptorQError::InvalidTransferLength(transfer_length));
+        }
+        if alignment == 0 || symbol_size == 0 || !symbol_size.is_multiple_of(alignment as u16) {
+            return Err(RaptorQError::InvalidSymbolSize {
+                symbol_size,
+                alignment,
+            });
+        }
+        if sub_blocks == 0 || sub_blocks > symbol_size / alignment as u16 {
+            return Err(RaptorQError::InvalidSubBlocks(sub_blocks));
+        }
+        let kt = transfer_length.div_ceil(symbol_size as u64);
+        if source_blocks == 0 || kt < source_blocks as u64 {
+            return Err(RaptorQError::InvalidSourceBlocks(source_blocks));
+        }
+        let max_k = kt.div_ceil(source_blocks as u64);
+        if max_k > MAX_SOURCE_SYMBOLS as u64 {
+            return Err(RaptorQError::TooManySourceSymbols(max_k));
+        }
+        Ok(ObjectTransmissionInformation {
+            transfer_length,
+            symbol_size,
+            source_blocks,
+            sub_blocks,
+            alignment,
+        })
+    }
+
+    /// Picks parameters for an object of `transfer_length` bytes sent in
+    /// symbols of `symbol_size` bytes: no sub-blocking, and as few
+    /// source blocks as possible while keeping each below 4 MiB.
+    pub fn with_defaults(transfer_length: u64, symbol_size: u16) -> Result<Self, RaptorQError> {
+        if symbol_size == 0 {
+            return Err(RaptorQError::InvalidSymbolSize {
+                symbol_size,
+                alignment: 1,
+            });
+        }
+        let max_k = (DEFAULT_MAX_BLOCK_BYTES / symbol_size as usize).clamp(1, MAX_SOURCE_SYMBOLS);
+        let kt = transfer_length.div_ceil(symbol_size as u64);
+        let z = kt.div_ceil(max_k as u64).max(1);
+        if z > u8::MAX as u64 {
+            return Err(RaptorQError::TooManySourceBlocks(z));
+        }
+        Self::new(transfer_length, symbol_size, z as u8, 1, 1)
+    }
+
+    /// The transfer length F in bytes.
+    pub fn transfer_length(&self) -> u64 {
+        self.transfer_length
+    }
+
+    /// The symbol size T in bytes.
+    pub fn symbol_size(&self) -> u16 {
+        self.symbol_size
+    }
+
+    /// The number of source blocks Z.
+    pub fn source_blocks(&self) -> u8 {
+        self.source_blocks
+    }
+
+    /// The number of sub-blocks N.
+    pub fn sub_blocks(&self) -> u16 {
+        self.sub_blocks
+    }
+
+    /// The symbol alignment Al.
+    pub fn alignment(&self) -> u8 {
+        self.alignment
+    }
+
+    /// Serializes the Common and Scheme-Specific FEC OTI of sections
+    /// 3.3.2 and 3.3.3.
+    pub fn to_bytes(&self) -> [u8; 12] {
+        let f = self.transfer_length.to_be_bytes();
+        let t = self.symbol_size.to_be_bytes();
+        let n = self.sub_blocks.to_be_bytes();
+        [
+            f[3],
+            f[4],
+            f[5],
+            f[6],
+            f[7],
+            0,
+            t[0],
+            t[1],
+            self.source_blocks,
+            n[0],
+            n[1],
+            self.alignment,
+        ]
+    }
+
+    /// Parses and validates a serialized OTI.
+    pub fn from_bytes(buf: &[u8]) -> Result<Self, RaptorQError> {
+        if buf.len() < Self::ENCODED_LEN {
+            return Err(RaptorQError::Truncated {
+                needed: Self::ENCODED_LEN,
+                available: buf.len(),
+            });
+        }
+        let mut f = [0; 8];
+        f[3..].copy_from_slice(&buf[..5]);
+        Self::new(
+            u64::from_be_bytes(f),
+            u16::from_be_bytes([buf[6], buf[7]]),
+            buf[8],
+            u16::from_be_bytes([buf[9], buf[10]]),
+            buf[11],
+        )
+    }
+
+    /// Number of source symbols in each source block.
+    fn block_symbols(&self) -> Vec<usize> {
+        let kt = self.transfer_length.div_ceil(self.symbol_size as u64) as usize;
+        let (kl, ks, zl, zs) = partition(kt, self.source_blocks as usize);
+        let mut sizes = vec![kl; zl];
+        sizes.extend(std::iter::repeat_n(ks, zs));
+        sizes
+    }
+
+    /// Sub-symbol size of each sub-block.
+    fn sub_symbol_sizes(&self) -> Vec<usize> {
+        let al = self.alignment as usize;
+        let (tl, ts, nl, ns) = partition(self.symbol_size as usize / al, self.sub_blocks as usize);
+        let mut sizes = vec![tl * al; nl];
+        sizes.extend(std::iter::repeat_n(ts * al, ns));
+        sizes
+    }
+
+    /// The layout of every source block and its byte offset in the
+    /// object.
+    fn layouts(&self) -> Vec<(usize, BlockLayout)> {
+        let sub_sizes = self.sub_symbol_sizes();
+        let mut offset = 0;
+        self.block_symbols()
+            .into_iter()
+            .map(|k| {
+                let layout = BlockLayout {
+                    k,
+                    sub_sizes: sub_sizes.clone(),
+                };
+                let begin = offset;
+                offset += k * self.symbol_size as usize;
+                (begin, layout)
+            })
+            .collect()
+    }
+}
+
+/// Partition[I, J] of section 4.4.1.2: splits I into J parts, the first
+/// JL of size IL and the remaining JS of size IS.
+pub(crate) fn partition(i: usize, j: usize) -> (usize, usize, usize, usize) {
+    let il = i.div_ceil(j);
+    let is = i / j;
+    let jl = i - is * j;
+    let js = j - jl;
+    (il, is, jl, js)
+}
+
+/// How the bytes of a source block map onto its symbols. With N
+/// sub-blocks, symbol i is the concatenation of sub-symbol i of every
+/// sub-block.
+#[derive(Debug, Clone)]
+struct BlockLayout {
+    k: usize,
+    sub_sizes: Vec<usize>,
+}
+
+impl BlockLayout {
+    fn symbol_size(&self) -> usize {
+        self.sub_sizes.iter().sum()
+    }
+
+    /// Splits the block, zero padded to K * T bytes, into symbols.
+    fn to_symbols(&self, block: &[u8]) -> Vec<Vec<u8>> {
+        let mut symbols = vec![Vec::with_capacity(self.symbol_size()); self.k];
+        let mut offset = 0;
+        for &size in &self.sub_sizes {
+            for (i, symbol) in symbols.iter_mut().enumerate() {
+                let begin = (offset + i * size).min(block.len());
+                let end = (offset + (i + 1) * size).min(block.len());
+                symbol.extend_from_slice(&block[begin..end]);
+                symbol.resize(symbol.len() + size - (end - begin), 0);
+            }
+            offset += self.k * size;
+        }
+        symbols
+    }
+
+    /// Inverse of `to_symbols`, writes at most `out.len()` bytes.
+    fn write_symbols(&self, symbols: &[Vec<u8>], out: &mut [u8]) {
+        let mut offset = 0;
+        let mut sub_offset = 0;
+        for &size in &self.sub_sizes {
+            for (i, symbol) in symbols.iter().enumerate() {
+                let begin = (offset + i * size).min(out.len());
+                let end = (offset + (i + 1) * size).min(out.len());
+                out[begin..end].copy_from_slice(&symbol[sub_offset..sub_offset + end - begin]);
+            }
+            offset += self.k * size;
+            sub_offset += size;
+        }
+    }
+}
+
+/// The FEC payload ID of section 3.2: source block number and encoding
+/// symbol id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PayloadId {
+    sbn: u8,
+    esi: u32,
+}
+
+impl PayloadId {
+    /// # Panics
+    ///
+    /// Panics if `esi` does not fit into 24 bits.
+    pub fn new(sbn: u8, esi: u32) -> Self {
+        assert!(esi <= MAX_ESI, "encoding symbol id exceeds 24 bits");
+        PayloadId { sbn, esi }
+    }
+
+    /// The source block number.
+    pub fn sbn(&self) -> u8 {
+        self.sbn
+    }
+
+    /// The encoding symbol id.
+    pub fn esi(&self) -> u32 {
+        self.esi
+    }
+
+    pub fn to_bytes(&self) -> [u8; 4] {
+        let esi = self.esi.to_be_bytes();
+        [self.sbn, esi[1], esi[2], esi[3]]
+    }
+
+    pub fn from_bytes(buf: &[u8]) -> Result<Self, RaptorQError> {
+        if buf.len() < 4 {
+            return Err(RaptorQError::Truncated {
+                needed: 4,
+                available: buf.len(),
+            });
+        }
+        Ok(PayloadId {
+            sbn: buf[0],
+            esi: read_u32(buf) & MAX_ESI,
+        })
+    }
+}
+
+/// An encoding symbol together with its payload ID, the unit sent over
+/// the network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodingPacket {
+    pub payload_id: PayloadId,
+    pub data: Vec<u8>,
+}
+
+impl EncodingPacket {
+    pub fn new(payload_id: PayloadId, data: Vec<u8>) -> Self {
+        EncodingPacket { payload_id, data }
+    }
+
+    /// Serializes the packet as its payload ID followed by the symbol.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(4 + self.data.len());
+        buf.extend_from_slice(&self.payload_id.to_bytes());
+        buf.extend_from_slice(&self.data);
+        buf
+    }
+
+    pub fn from_bytes(buf: &[u8]) -> Result<Self, RaptorQError> {
+        let payload_id = PayloadId::from_bytes(buf)?;
+        Ok(EncodingPacket::new(payload_id, buf[4..].to_vec()))
+    }
+}
+
+/// Errors raised for invalid RaptorQ parameters or input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RaptorQError {
+    /// The input ended before the structure was complete.
+    Truncated { needed: usize, available: usize },
+    /// The transfer length is zero or exceeds [`MAX_TRANSFER_LENGTH`].
+    InvalidTransferLength(u64),
+    /// The symbol size is zero or not a multiple of the alignment.
+    InvalidSymbolSize { symbol_size: u16, alignment: u8 },
+    /// The number of sub-blocks is zero or leaves sub-symbols smaller
+    /// than the alignment.
+    InvalidSubBlocks(u16),
+    /// The number of source blocks is zero or larger than the number of
+    /// symbols.
+    InvalidSourceBlocks(u8),
+    /// The object needs more than 255 source blocks.
+    TooManySourceBlocks(u64),
+    /// A source block would hold more than [`MAX_SOURCE_SYMBOLS`].
+    TooManySourceSymbols(u64),
+    /// The data does not match the transfer length of the OTI.
+    LengthMismatch { expected: u64, actual: usize },
+}
+
+impl fmt::Display for RaptorQError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RaptorQError::Truncated { needed, available } => {
+                write!(
+                    f,
+                    "truncated input: need {} bytes, got {}",
+                    needed, available
+                )
+            }
+            RaptorQError::InvalidTransferLength(len) => {
+                write!(f, "invalid transfer length {}", len)
+            }
+            RaptorQError::InvalidSymbolSize {
+                symbol_size,
+                alignment,
+            } => write!(
+                f,
+                "invalid symbol size {} for alignment {}",
+                symbol_size, alignment
+            ),
+            RaptorQError::InvalidSubBlocks(n) => write!(f, "invalid number of sub-blocks {}", n),
+            RaptorQError::InvalidSourceBlocks(z) => {
+                write!(f, "invalid number of source blocks {}", z)
+            }
+            RaptorQError::TooManySourceBlocks(z) => {
+                write!(f, "object needs {} source blocks, at most 255 allowed", z)
+            }
+            RaptorQError::TooManySourceSymbols(k) => write!(
+                f,
+                "source block of {} symbols exceeds the maximum of {}",
+                k, MAX_SOURCE_SYMBOLS
+            ),
+            RaptorQError::LengthMismatch { expected, actual } => write!(
+                f,
+                "data is {} bytes, transfer length is {}",
+                actual, expected
+            ),
+        }
+    }
+}
+
+impl error::Error for RaptorQError {}
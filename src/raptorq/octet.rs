@@ -0,0 +1,79 @@
+// Arithmetic in GF(256) as defined in section 5.7 of RFC 6330,
+// generated by the irreducible polynomial x^8 + x^4 + x^3 + x^2 + 1.
+use crate::xor::xor_bytes;
+
+const POLY: u16 = 0x11d;
+
+// alpha^i for 0 <= i < 510, long enough to skip the modulo in `mul`.
+static EXP: [u8; 510] = build_exp();
+static LOG: [u8; 256] = build_log();
+
+const fn build_exp() -> [u8; 510] {
+    let mut table = [0; 510];
+    let mut x: u16 = 1;
+    let mut i = 0;
+    while i < 510 {
+        table[i] = x as u8;
+        x <<= 1;
+        if x & 0x100 != 0 {
+            x ^= POLY;
+        }
+        i += 1;
+    }
+    table
+}
+
+const fn build_log() -> [u8; 256] {
+    let exp = build_exp();
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 255 {
+        table[exp[i] as usize] = i as u8;
+        i += 1;
+    }
+    table
+}
+
+/// alpha^i
+pub fn alpha_pow(i: usize) -> u8 {
+    EXP[i % 255]
+}
+
+pub fn mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        0
+    } else {
+        EXP[LOG[a as usize] as usize + LOG[b as usize] as usize]
+    }
+}
+
+/// Multiplicative inverse, `a` must not be zero.
+pub fn inv(a: u8) -> u8 {
+    EXP[255 - LOG[a as usize] as usize]
+}
+
+/// `dst += c * src`
+pub fn fma_slice(dst: &mut [u8], src: &[u8], c: u8) {
+    match c {
+        0 => {}
+        1 => xor_bytes(dst, src),
+        _ => {
+            let log_c = LOG[c as usize] as usize;
+            for (d, &s) in dst.iter_mut().zip(src) {
+                if s != 0 {
+                    *d ^= EXP[LOG[s as usize] as usize + log_c];
+                }
+            }
+        }
+    }
+}
+
+/// `dst *= c`
+pub fn scale_slice(dst: &mut [u8], c: u8) {
+    if c == 1 {
+        return;
+    }
+    for d in dst.iter_mut() {
+        *d = mul(*d, c);
+    }
+}
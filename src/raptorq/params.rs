@@ -0,0 +1,236 @@
+// Per source block parameters, the tuple generator and the precode
+// constraints of RFC 6330 section 5.3.
+use super::{
+    octet,
+    tables::{DEGREE_TABLE, SYSTEMATIC_INDICES, V0, V1, V2, V3},
+};
+
+/// Largest number of source symbols in a source block, K'_max.
+pub const MAX_SOURCE_SYMBOLS: usize = 56403;
+
+/// Rand[y, i, m] of section 5.3.5.1
+pub fn rand(y: u32, i: u32, m: u32) -> u32 {
+    let x0 = (y.wrapping_add(i) & 0xff) as usize;
+    let x1 = ((y >> 8).wrapping_add(i) & 0xff) as usize;
+    let x2 = ((y >> 16).wrapping_add(i) & 0xff) as usize;
+    let x3 = ((y >> 24).wrapping_add(i) & 0xff) as usize;
+    (V0[x0] ^ V1[x1] ^ V2[x2] ^ V3[x3]) % m
+}
+
+/// The (d, a, b, d1, a1, b1) tuple of section 5.3.5.4
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tuple {
+    pub d: u32,
+    pub a: u32,
+    pub b: u32,
+    pub d1: u32,
+    pub a1: u32,
+    pub b1: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct BlockParams {
+    /// Number of source symbols, K
+    pub k: usize,
+    /// Extended number of source symbols, K'
+    pub k_prime: usize,
+    /// Systematic index J(K')
+    pub j: usize,
+    /// Number of LDPC symbols, S
+    pub s: usize,
+    /// Number of HDPC symbols, H
+    pub h: usize,
+    /// Number of LT symbols, W
+    pub w: usize,
+    /// Number of intermediate symbols, L
+    pub l: usize,
+    /// Number of permanently inactivated symbols, P
+    pub p: usize,
+    /// Smallest prime not below P
+    pub p1: usize,
+}
+
+impl BlockParams {
+    /// `k` must be between 1 and `MAX_SOURCE_SYMBOLS`.
+    pub fn new(k: usize) -> Self {
+        let &(k_prime, j, s, h, w) = SYSTEMATIC_INDICES
+            .iter()
+            .find(|row| row.0 as usize >= k)
+            .expect("too many source symbols");
+        let (k_prime, j, s, h, w) = (
+            k_prime as usize,
+            j as usize,
+            s as usize,
+            h as usize,
+            w as usize,
+        );
+        let l = k_prime + s + h;
+        let p = l - w;
+        let p1 = (p..).find(|&n| is_prime(n)).unwrap();
+        BlockParams {
+            k,
+            k_prime,
+            j,
+            s,
+            h,
+            w,
+            l,
+            p,
+            p1,
+        }
+    }
+
+    /// Tuple[K', X] for the internal symbol id `x`.
+    pub fn tuple(&self, x: u32) -> Tuple {
+        let j = self.j as u32;
+        let w = self.w as u32;
+        let p1 = self.p1 as u32;
+
+        let mut a = 53591 + j * 997;
+        if a.is_multiple_of(2) {
+            a += 1;
+        }
+        let b = 10267 * (j + 1);
+        let y = b.wrapping_add(x.wrapping_mul(a));
+        let v = rand(y, 0, 1 << 20);
+        let d = deg(v, w);
+        let a = 1 + rand(y, 1, w - 1);
+        let b = rand(y, 2, w);
+        let d1 = if d < 4 { 2 + rand(x, 3, 2) } else { 2 };
+        let a1 = 1 + rand(x, 4, p1 - 1);
+        let b1 = rand(x, 5, p1);
+        Tuple {
+            d,
+            a,
+            b,
+            d1,
+            a1,
+            b1,
+        }
+    }
+
+    /// Indices of the intermediate symbols that Enc[K', C, tuple] of
+    /// section 5.3.5.3 adds up. The indices are distinct.
+    pub fn enc_indices(&self, t: Tuple) -> Vec<usize> {
+        let w = self.w as u32;
+        let p = self.p as u32;
+        let p1 = self.p1 as u32;
+        let mut indices = Vec::with_capacity((t.d + t.d1) as usize);
+
+        let mut b = t.b;
+        indices.push(b as usize);
+        for _ in 1..t.d {
+            b = (b + t.a) % w;
+            indices.push(b as usize);
+        }
+
+        let mut b1 = t.b1;
+        while b1 >= p {
+            b1 = (b1 + t.a1) % p1;
+        }
+        indices.push((w + b1) as usize);
+        for _ in 1..t.d1 {
+            b1 = (b1 + t.a1) % p1;
+            while b1 >= p {
+                b1 = (b1 + t.a1) % p1;
+            }
+            indices.push((w + b1) as usize);
+        }
+        indices
+    }
+
+    /// Intermediate symbols making up the encoding symbol with internal
+    /// symbol id `isi`.
+    pub fn isi_indices(&self, isi: u32) -> Vec<usize> {
+        self.enc_indices(self.tuple(isi))
+    }
+
+    /// The S LDPC constraints of section 5.3.3.3. Each row lists the
+    /// intermediate symbols that add up to zero.
+    pub fn ldpc_rows(&self) -> Vec<Vec<usize>> {
+        let (s, w, p) = (self.s, self.w, self.p);
+        let b_cnt = w - s;
+        let mut rows = vec![Vec::new(); s];
+
+        for i in 0..b_cnt {
+            let a = 1 + i / s;
+            let mut b = i % s;
+            rows[b].push(i);
+            b = (b + a) % s;
+            rows[b].push(i);
+            b = (b + a) % s;
+            rows[b].push(i);
+        }
+        for (i, row) in rows.iter_mut().enumerate() {
+            row.push(b_cnt + i);
+            row.push(w + i % p);
+            row.push(w + (i + 1) % p);
+        }
+
+        // The constraints add symbols over GF(2), so a symbol listed
+        // twice cancels out.
+        for row in rows.iter_mut() {
+            row.sort_unstable();
+            let mut reduced: Vec<usize> = Vec::with_capacity(row.len());
+            for &c in row.iter() {
+                if reduced.last() == Some(&c) {
+                    reduced.pop();
+                } else {
+                    reduced.push(c);
+                }
+            }
+            *row = reduced;
+        }
+        rows
+    }
+
+    /// The H HDPC constraints of section 5.3.3.3 as dense rows over
+    /// all L intermediate symbols.
+    pub fn hdpc_rows(&self) -> Vec<Vec<u8>> {
+        let (h, l) = (self.h, self.l);
+        let n = self.k_prime + self.s;
+        let mut rows = vec![vec![0_u8; l]; h];
+
+        // G_HDPC = MT * GAMMA, computed column by column from the right:
+        // G[., j] = MT[., j] + alpha * G[., j + 1]
+        for (i, row) in rows.iter_mut().enumerate() {
+            row[n - 1] = octet::alpha_pow(i);
+        }
+        for j in (0..n - 1).rev() {
+            for row in rows.iter_mut() {
+                row[j] = octet::mul(2, row[j + 1]);
+            }
+            let r1 = rand(j as u32 + 1, 6, h as u32) as usize;
+            let r2 = (r1 + rand(j as u32 + 1, 7, h as u32 - 1) as usize + 1) % h;
+            rows[r1][j] ^= 1;
+            rows[r2][j] ^= 1;
+        }
+        for (i, row) in rows.iter_mut().enumerate() {
+            row[n + i] = 1;
+        }
+        rows
+    }
+}
+
+/// Deg[v] of section 5.3.5.2
+fn deg(v: u32, w: u32) -> u32 {
+    let d = DEGREE_TABLE
+        .iter()
+        .position(|&f| v < f)
+        .expect("v must be below 2^20") as u32;
+    d.min(w - 2)
+}
+
+fn is_prime(n: usize) -> bool {
+    if n < 2 {
+        return false;
+    }
+    let mut i = 2;
+    while i * i <= n {
+        if n.is_multiple_of(i) {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
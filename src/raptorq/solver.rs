@@ -0,0 +1,223 @@
+// Solves the RaptorQ constraint system A * C = D for the intermediate
+// symbols C.
+//
+// RFC 6330 does not mandate a decoding algorithm, any method that finds
+// C yields the same symbols. We use inactivation decoding in the spirit
+// of section 5.4: the PI symbols start out inactivated, the binary
+// equations are peeled, inactivating further symbols whenever peeling
+// stalls, and the few inactivated symbols are solved densely over
+// GF(256) together with the HDPC equations.
+use super::octet;
+use crate::xor::xor_bytes;
+
+/// A GF(2) equation: the symbols listed in `cols` add up to `data`.
+pub struct BinaryRow {
+    pub cols: Vec<usize>,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Col {
+    Active,
+    Peeled,
+    Inactive(usize),
+}
+
+/// Returns the `l` intermediate symbols, or `None` if the equations do
+/// not determine all of them. The symbols from `first_inactive` on are
+/// inactivated from the start. `hdpc` holds dense GF(256) equations
+/// over all `l` symbols that add up to zero.
+pub fn solve(
+    l: usize,
+    first_inactive: usize,
+    symbol_size: usize,
+    rows: Vec<BinaryRow>,
+    hdpc: &[Vec<u8>],
+) -> Option<Vec<Vec<u8>>> {
+    let mut state = vec![Col::Active; l];
+    let mut cnt_inactive = 0;
+    for s in state.iter_mut().skip(first_inactive) {
+        *s = Col::Inactive(cnt_inactive);
+        cnt_inactive += 1;
+    }
+
+    let mut adj = vec![Vec::new(); l];
+    let mut active_deg = vec![0; rows.len()];
+    for (r, row) in rows.iter().enumerate() {
+        for &c in &row.cols {
+            adj[c].push(r);
+            if state[c] == Col::Active {
+                active_deg[r] += 1;
+            }
+        }
+    }
+
+    // Symbolic phase: decide the peeling order and which symbols to
+    // inactivate, without touching any symbol data.
+    let mut used = vec![false; rows.len()];
+    let mut order = Vec::new();
+    let mut ones: Vec<usize> = (0..rows.len()).filter(|&r| active_deg[r] == 1).collect();
+    loop {
+        while let Some(r) = ones.pop() {
+            if used[r] || active_deg[r] != 1 {
+                continue;
+            }
+            let c = *rows[r]
+                .cols
+                .iter()
+                .find(|&&c| state[c] == Col::Active)
+                .unwrap();
+            used[r] = true;
+            state[c] = Col::Peeled;
+            order.push((r, c));
+            release(c, &adj, &used, &mut active_deg, &mut ones);
+        }
+
+        let stalled = (0..rows.len())
+            .filter(|&r| !used[r] && active_deg[r] >= 2)
+            .min_by_key(|&r| active_deg[r]);
+        let r = match stalled {
+            Some(r) => r,
+            None => break,
+        };
+        // Inactivate all but one symbol of the sparsest row, which
+        // turns it into a degree one row.
+        let cols: Vec<usize> = rows[r]
+            .cols
+            .iter()
+            .cloned()
+            .filter(|&c| state[c] == Col::Active)
+            .skip(1)
+            .collect();
+        for c in cols {
+            state[c] = Col::Inactive(cnt_inactive);
+            cnt_inactive += 1;
+            release(c, &adj, &used, &mut active_deg, &mut ones);
+        }
+    }
+    // Symbols no binary equation could resolve are left to the dense
+    // phase.
+    for s in state.iter_mut().filter(|s| **s == Col::Active) {
+        *s = Col::Inactive(cnt_inactive);
+        cnt_inactive += 1;
+    }
+
+    // Numeric phase: express every peeled symbol as its row's data plus
+    // a combination of inactive symbols.
+    let words = cnt_inactive.div_ceil(64);
+    let mut expr_data = vec![Vec::new(); l];
+    let mut expr_coef = vec![Vec::new(); l];
+    let mut rows: Vec<Option<BinaryRow>> = rows.into_iter().map(Some).collect();
+    for &(r, c) in &order {
+        let row = rows[r].take().unwrap();
+        let mut data = row.data;
+        let mut coef = vec![0_u64; words];
+        for &col in &row.cols {
+            match state[col] {
+                _ if col == c => {}
+                Col::Inactive(i) => coef[i / 64] ^= 1 << (i % 64),
+                Col::Peeled => {
+                    xor_bytes(&mut data, &expr_data[col]);
+                    for (a, b) in coef.iter_mut().zip(&expr_coef[col]) {
+                        *a ^= *b;
+                    }
+                }
+                Col::Active => unreachable!(),
+            }
+        }
+        expr_data[c] = data;
+        expr_coef[c] = coef;
+    }
+
+    // Dense phase over the inactive symbols, fed by the unused binary
+    // rows and the HDPC rows.
+    let mut dense: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
+    for row in rows.into_iter().flatten() {
+        let mut coef = vec![0_u8; cnt_inactive];
+        let mut data = row.data;
+        for &col in &row.cols {
+            match state[col] {
+                Col::Inactive(i) => coef[i] ^= 1,
+                _ => {
+                    xor_bytes(&mut data, &expr_data[col]);
+                    for_each_bit(&expr_coef[col], |i| coef[i] ^= 1);
+                }
+            }
+        }
+        dense.push((coef, data));
+    }
+    for h in hdpc {
+        let mut coef = vec![0_u8; cnt_inactive];
+        let mut data = vec![0_u8; symbol_size];
+        for (col, &beta) in h.iter().enumerate().filter(|(_, &b)| b != 0) {
+            match state[col] {
+                Col::Inactive(i) => coef[i] ^= beta,
+                _ => {
+                    octet::fma_slice(&mut data, &expr_data[col], beta);
+                    for_each_bit(&expr_coef[col], |i| coef[i] ^= beta);
+                }
+            }
+        }
+        dense.push((coef, data));
+    }
+
+    for col in 0..cnt_inactive {
+        let pivot = (col..dense.len()).find(|&r| dense[r].0[col] != 0)?;
+        dense.swap(col, pivot);
+        let (mut p_coef, mut p_data) = std::mem::take(&mut dense[col]);
+        let inv = octet::inv(p_coef[col]);
+        octet::scale_slice(&mut p_coef[col..], inv);
+        octet::scale_slice(&mut p_data, inv);
+        for (coef, data) in dense.iter_mut() {
+            let beta = match coef.get(col) {
+                Some(&b) if b != 0 => b,
+                _ => continue,
+            };
+            octet::fma_slice(&mut coef[col..], &p_coef[col..], beta);
+            octet::fma_slice(data, &p_data, beta);
+        }
+        dense[col] = (p_coef, p_data);
+    }
+    dense.truncate(cnt_inactive);
+    let inactive: Vec<Vec<u8>> = dense.into_iter().map(|(_, data)| data).collect();
+
+    let symbols = (0..l)
+        .map(|c| match state[c] {
+            Col::Inactive(i) => inactive[i].clone(),
+            _ => {
+                let mut data = std::mem::take(&mut expr_data[c]);
+                for_each_bit(&expr_coef[c], |i| xor_bytes(&mut data, &inactive[i]));
+                data
+            }
+        })
+        .collect();
+    Some(symbols)
+}
+
+// Marks column `c` as no longer active for all unused rows containing it.
+fn release(
+    c: usize,
+    adj: &[Vec<usize>],
+    used: &[bool],
+    active_deg: &mut [usize],
+    ones: &mut Vec<usize>,
+) {
+    for &r in &adj[c] {
+        if !used[r] {
+            active_deg[r] -= 1;
+            if active_deg[r] == 1 {
+                ones.push(r);
+            }
+        }
+    }
+}
+
+fn for_each_bit<F: FnMut(usize)>(words: &[u64], mut f: F) {
+    for (w, &word) in words.iter().enumerate() {
+        let mut word = word;
+        while word != 0 {
+            f(w * 64 + word.trailing_zeros() as usize);
+            word &= word - 1;
+        }
+    }
+}
@@ -1,4 +1,6 @@
+use crate::distribution::DegreeDistribution;
 use rand::{distributions::Distribution, Rng};
+use std::{cmp, error, fmt};
 
 #[derive(Debug, Clone)]
 pub enum Soliton {
@@ -17,6 +19,8 @@ pub enum Soliton {
         beta: f32,
         // spike position, usually: k/R unless configured manually as a tuning parameter
         m: usize,
+        // cumulative probabilities of the degrees 1..=k
+        cdf: Vec<f32>,
     },
 }
 
@@ -27,30 +31,73 @@ impl Soliton {
         }
     }
 
+    /// Like [`Soliton::try_robust`], but panics on invalid parameters.
     pub fn robust(k: usize, c: f32, spike: Option<usize>, delta: f32) -> Self {
-        if let Some(m) = spike {
+        match Self::try_robust(k, c, spike, delta) {
+            Ok(sol) => sol,
+            Err(err) => panic!("invalid Robust Soliton parameters: {}", err),
+        }
+    }
+
+    /// The Robust Soliton distribution of Luby's "LT codes" for `k`
+    /// blocks, with `R = c ln(k / delta) sqrt(k)` and the spike at
+    /// `k / R`, clamped to `1..=k`.
+    ///
+    /// `spike` places the spike manually instead, then `R = k / spike`.
+    /// `c` must be positive, `delta` within `(0, 1)` and `spike` within
+    /// `1..=k`.
+    pub fn try_robust(
+        k: usize,
+        c: f32,
+        spike: Option<usize>,
+        delta: f32,
+    ) -> Result<Self, SolitonError> {
+        check_robust(k, c, spike, delta)?;
+        let (r, m) = match spike {
             // Spike position was given, use that instead of calculating
-            let r = k as f32 / m as f32;
-            let beta = compute_beta(k, m, r, delta);
-            Self::Robust {
-                k,
-                c,
-                r,
-                delta,
-                beta,
-                m,
+            Some(m) => (k as f32 / m as f32, m),
+            None => {
+                let r = compute_r(k, c, delta);
+                (r, compute_m(k, r))
+            }
+        };
+        let beta = compute_beta(k, m, r, delta);
+        let cdf = compute_cdf(k, m, r, delta, beta);
+        Ok(Self::Robust {
+            k,
+            c,
+            r,
+            delta,
+            beta,
+            m,
+            cdf,
+        })
+    }
+
+    /// The probability of drawing `degree`.
+    pub fn pmf(&self, degree: usize) -> f32 {
+        match self {
+            Self::Ideal { .. } => {
+                let k = self.max_degree();
+                if degree >= 1 && degree <= k {
+                    rho(k, degree)
+                } else {
+                    0.0
+                }
             }
-        } else {
-            let r = compute_r(k, c, delta);
-            let m = compute_m(k, r);
-            let beta = compute_beta(k, m, r, delta);
             Self::Robust {
                 k,
-                c,
                 r,
                 delta,
                 beta,
                 m,
+                ..
+            } => {
+                if degree >= 1 && degree <= *k {
+                    (rho(*k, degree) + tau(degree, *k, *m, *r, *delta)) / beta
+                } else {
+                    0.0
+                }
             }
         }
     }
@@ -67,50 +114,121 @@ impl Distribution<usize> for Soliton {
                     1
                 }
             }
-            Self::Robust {
-                k,
-                c: _c,
-                r,
-                delta,
-                beta,
-                m,
-            } => {
-                let mut sum = 0.0;
-                let mut index = 1;
-                let u = rng.gen::<f32>();
+            Self::Robust { k, cdf, .. } => {
+                // The smallest degree whose cumulative probability
+                // exceeds u, found by binary search.
+                let u = rng.gen::<f32>() * cdf[cdf.len() - 1];
+                cmp::min(cdf.partition_point(|&p| p <= u) + 1, *k)
+            }
+        }
+    }
+}
 
-                while sum <= u {
-                    sum += (rho(*k, index) + tau(index as usize, *m, *r, *delta)) / *beta;
-                    index += 1;
-                }
-                index - 1
+impl DegreeDistribution for Soliton {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        Distribution::sample(self, rng)
+    }
+
+    fn pmf(&self, degree: usize) -> f32 {
+        Soliton::pmf(self, degree)
+    }
+
+    fn max_degree(&self) -> usize {
+        match self {
+            Self::Ideal { limit } => (1.0 / limit).round() as usize,
+            Self::Robust { k, .. } => *k,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SolitonError {
+    /// The distribution needs at least one block.
+    NoBlocks,
+    /// The constant c must be positive and finite.
+    InvalidC(f32),
+    /// The failure probability delta must be within (0, 1).
+    InvalidDelta(f32),
+    /// The spike must be within 1..=k.
+    InvalidSpike { spike: usize, k: usize },
+}
+
+impl fmt::Display for SolitonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolitonError::NoBlocks => write!(f, "the distribution needs at least one block"),
+            SolitonError::InvalidC(c) => write!(f, "c must be positive, got {}", c),
+            SolitonError::InvalidDelta(delta) => {
+                write!(f, "delta must be within (0, 1), got {}", delta)
+            }
+            SolitonError::InvalidSpike { spike, k } => {
+                write!(f, "spike must be within 1..={}, got {}", k, spike)
             }
         }
     }
 }
 
+impl error::Error for SolitonError {}
+
+/// Checks the Robust Soliton parameters like [`Soliton::try_robust`],
+/// without computing the distribution.
+pub(crate) fn check_robust(
+    k: usize,
+    c: f32,
+    spike: Option<usize>,
+    delta: f32,
+) -> Result<(), SolitonError> {
+    if k == 0 {
+        return Err(SolitonError::NoBlocks);
+    }
+    if !(c.is_finite() && c > 0.0) {
+        return Err(SolitonError::InvalidC(c));
+    }
+    if !(delta > 0.0 && delta < 1.0) {
+        return Err(SolitonError::InvalidDelta(delta));
+    }
+    match spike {
+        Some(m) if m == 0 || m > k => Err(SolitonError::InvalidSpike { spike: m, k }),
+        _ => Ok(()),
+    }
+}
+
 fn compute_r(k: usize, c: f32, delta: f32) -> f32 {
     c * ((k as f32) / delta).ln() * (k as f32).sqrt()
 }
 
 fn compute_m(k: usize, r: f32) -> usize {
-    ((k as f32) / r).floor() as usize
+    (((k as f32) / r).floor() as usize).clamp(1, k)
 }
 
+// Sums in f64, k terms in f32 lose too much precision.
 fn compute_beta(k: usize, m: usize, r: f32, delta: f32) -> f32 {
-    let mut sum = 0.0;
+    let mut sum = 0.0_f64;
 
-    for pos in 1..k {
-        sum += rho(k, pos) + tau(pos, m, r, delta)
+    for pos in 1..=k {
+        sum += (rho(k, pos) + tau(pos, k, m, r, delta)) as f64
     }
-    sum
+    sum as f32
+}
+
+fn compute_cdf(k: usize, m: usize, r: f32, delta: f32, beta: f32) -> Vec<f32> {
+    let mut sum = 0.0_f64;
+    (1..=k)
+        .map(|index| {
+            sum += ((rho(k, index) + tau(index, k, m, r, delta)) / beta) as f64;
+            sum as f32
+        })
+        .collect()
 }
 
-fn tau(index: usize, m: usize, r: f32, delta: f32) -> f32 {
+// R / (i k) below the spike, R ln(R / delta) / k at the spike m. R
+// is kept as is rather than derived from the floored m. The spike is
+// dropped where R < delta would make it negative.
+fn tau(index: usize, k: usize, m: usize, r: f32, delta: f32) -> f32 {
     if index >= 1 && index < m {
-        (1 / (index * m)) as f32
+        r / (index as f32 * k as f32)
     } else if index == m {
-        (r / delta).ln() as f32 / m as f32
+        (r * (r / delta).ln() / k as f32).max(0.0)
     } else {
         0.0
     }
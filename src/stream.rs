@@ -0,0 +1,409 @@
+//! Encoding objects too large to hold in memory.
+//!
+//! The [`StreamEncoder`] splits an `io::Read + io::Seek` source into
+//! source blocks of at most a given number of symbols, the same
+//! [`Partition`] an [`ObjectEncoder`](crate::object::ObjectEncoder)
+//! uses, and encodes every source block independently with its own LT
+//! [`Encoder`]. Only the source
+//! block currently being encoded is held in memory. The encoder cycles
+//! through the source blocks like a carousel: it emits a number of
+//! droplets for one source block, then moves on to the next, and starts
+//! over after the last one.
+//!
+//! Every droplet is tagged with the number of its source block. The
+//! [`StreamDecoder`] keeps one LT [`Decoder`] per source block in
+//! flight and writes every recovered source block straight into an
+//! `io::Write + io::Seek` sink.
+//!
+//! A [`TaggedDroplet`] is serialized as the source block number followed
+//! by the droplet in its [wire format](crate::droplet):
+//!
+//! ```text
+//! offset  size  field
+//! 0       4     source block number (u32, big-endian)
+//! 4       n     droplet
+//! ```
+//!
+//! # Example
+//!
+//! ```
+//! use fountaincode::{
+//!     encoder::EncoderType,
+//!     stream::{StreamDecoder, StreamEncoder},
+//! };
+//! use std::io::Cursor;
+//!
+//! let msg: Vec<u8> = (0..10_000).map(|i| i as u8).collect();
+//! let mut enc = StreamEncoder::new(Cursor::new(&msg), 64, 50, EncoderType::Systematic).unwrap();
+//! let mut dec = StreamDecoder::new(Cursor::new(Vec::new()), enc.len(), 64, 50);
+//!
+//! for drop in &mut enc {
+//!     if dec.catch(drop.unwrap()).unwrap() {
+//!         break;
+//!     }
+//! }
+//! assert_eq!(dec.into_inner().into_inner(), msg);
+//! ```
+use crate::{
+    builder::{ConfigError, EncoderBuilder},
+    decoder::{CatchResult, Decoder},
+    droplet::{Droplet, WireError},
+    encoder::{Encoder, EncoderType},
+    header::EdgeGenerator,
+    object::Partition,
+    prng::derive_seed,
+    wire::read_u32,
+};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+/// A droplet together with the number of its source block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaggedDroplet {
+    /// The source block number
+    pub sbn: u32,
+    pub droplet: Droplet,
+}
+
+impl TaggedDroplet {
+    pub fn new(sbn: u32, droplet: Droplet) -> TaggedDroplet {
+        TaggedDroplet { sbn, droplet }
+    }
+
+    /// Number of bytes the serialized droplet occupies.
+    pub fn encoded_len(&self) -> usize {
+        4 + self.droplet.encoded_len()
+    }
+
+    /// Serializes the droplet into `buf` without allocating and
+    /// returns the number of bytes written.
+    pub fn write_into(&self, buf: &mut [u8]) -> Result<usize, WireError> {
+        if buf.len() < self.encoded_len() {
+            return Err(WireError::BufferTooSmall {
+                needed: self.encoded_len(),
+                available: buf.len(),
+            });
+        }
+        buf[..4].copy_from_slice(&self.sbn.to_be_bytes());
+        Ok(4 + self.droplet.write_into(&mut buf[4..])?)
+    }
+
+    /// Serializes the droplet into a freshly allocated buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`Droplet::to_bytes`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = self.sbn.to_be_bytes().to_vec();
+        buf.extend_from_slice(&self.droplet.to_bytes());
+        buf
+    }
+
+    /// Parses a droplet previously produced by
+    /// [`TaggedDroplet::to_bytes`]. `buf` must contain exactly one
+    /// droplet.
+    pub fn from_bytes(buf: &[u8]) -> Result<TaggedDroplet, WireError> {
+        if buf.len() < 4 {
+            return Err(WireError::Truncated {
+                needed: 4,
+                available: buf.len(),
+            });
+        }
+        Ok(TaggedDroplet {
+            sbn: read_u32(&buf[..4]),
+            droplet: Droplet::from_bytes(&buf[4..])?,
+        })
+    }
+}
+
+/// The seed of the encoder of source block `sbn`, derived from the
+/// master seed like the droplets of an [`Encoder`].
+pub(crate) fn block_seed(seed: u64, sbn: usize) -> u64 {
+    derive_seed(seed, sbn as u64)
+}
+
+/// Encoder for objects read from an `io::Read + io::Seek` source.
+///
+/// Every source block is encoded with a seed derived from the master
+/// seed, so a source block read again continues its droplet sequence
+/// where the previous pass stopped instead of starting over. In
+/// systematic mode the later passes thus carry repair droplets.
+///
+/// As an `Iterator` it yields the droplets of all source blocks
+/// forever, or the first read error.
+pub struct StreamEncoder<R> {
+    reader: R,
+    // Position of the object in the reader
+    begin: u64,
+    partition: Partition,
+    builder: EncoderBuilder<()>,
+    seed: u64,
+    droplets_per_block: usize,
+    current: Option<(u32, Encoder)>,
+    cnt_in_block: usize,
+    // Passes over all source blocks completed so far
+    pass: usize,
+}
+
+impl StreamEncoder<File> {
+    /// Encodes the file at `path`.
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        blocksize: usize,
+        max_block_symbols: usize,
+        encodertype: EncoderType,
+    ) -> io::Result<Self> {
+        StreamEncoder::new(File::open(path)?, blocksize, max_block_symbols, encodertype)
+    }
+}
+
+impl<R: Read + Seek> StreamEncoder<R> {
+    /// Encodes everything from the current position of `reader` to its
+    /// end, in source blocks of at most `max_block_symbols` symbols of
+    /// `blocksize` bytes, with the Ideal Soliton distribution.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `blocksize` or `max_block_symbols` is zero.
+    pub fn new(
+        reader: R,
+        blocksize: usize,
+        max_block_symbols: usize,
+        encodertype: EncoderType,
+    ) -> io::Result<Self> {
+        assert!(blocksize > 0, "blocksize must not be zero");
+        assert!(max_block_symbols > 0, "max_block_symbols must not be zero");
+        let builder = EncoderBuilder::new(())
+            .blocksize(blocksize)
+            .encoder_type(encodertype)
+            .ideal();
+        StreamEncoder::from_builder(reader, max_block_symbols, builder)
+    }
+
+    /// Like [`StreamEncoder::new`], but encodes every source block with
+    /// the settings of `builder`, whose data is ignored. The seed of
+    /// `builder`, or a random one, becomes the master seed of the
+    /// stream. Invalid settings are reported as
+    /// `io::ErrorKind::InvalidInput` wrapping a [`ConfigError`].
+    pub fn from_builder<S>(
+        mut reader: R,
+        max_block_symbols: usize,
+        builder: EncoderBuilder<S>,
+    ) -> io::Result<Self> {
+        let invalid = |err: ConfigError| io::Error::new(io::ErrorKind::InvalidInput, err);
+        let builder = builder.data(());
+        let begin = reader.stream_position()?;
+        let end = reader.seek(SeekFrom::End(0))?;
+        let partition =
+            Partition::new(end - begin, builder.blocksize, max_block_symbols).map_err(invalid)?;
+        // The first source blocks are the largest, the last ones the
+        // smallest
+        if let Some(last) = partition.cnt_source_blocks().checked_sub(1) {
+            for sbn in &[0, last] {
+                let block = partition.block(*sbn);
+                builder
+                    .distribution((block.end - block.start) as usize)
+                    .map_err(invalid)?;
+            }
+        }
+        Ok(StreamEncoder {
+            reader,
+            begin,
+            partition,
+            seed: builder.seed.unwrap_or_else(rand::random),
+            builder,
+            droplets_per_block: 2 * max_block_symbols,
+            current: None,
+            cnt_in_block: 0,
+            pass: 0,
+        })
+    }
+
+    /// Sets how many droplets are sent for a source block before the
+    /// encoder moves on to the next one, twice the maximum number of
+    /// symbols by default. Raise it for lossy channels.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `droplets_per_block` is zero.
+    pub fn with_droplets_per_block(mut self, droplets_per_block: usize) -> Self {
+        assert!(
+            droplets_per_block > 0,
+            "droplets_per_block must not be zero"
+        );
+        self.droplets_per_block = droplets_per_block;
+        self
+    }
+
+    /// Length of the object in bytes.
+    pub fn len(&self) -> u64 {
+        self.partition.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.partition.is_empty()
+    }
+
+    pub fn cnt_source_blocks(&self) -> usize {
+        self.partition.cnt_source_blocks()
+    }
+
+    pub fn partitioning(&self) -> &Partition {
+        &self.partition
+    }
+
+    /// The master seed the encoders of all source blocks are derived
+    /// from.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Reads source block `sbn` and returns an encoder for it. The
+    /// encoder of a source block always produces the same droplets.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sbn` is not a source block of the object.
+    pub fn source_block(&mut self, sbn: usize) -> io::Result<Encoder> {
+        assert!(sbn < self.cnt_source_blocks(), "no source block {}", sbn);
+        let block = self.partition.block(sbn);
+        let mut data = vec![0; (block.end - block.start) as usize];
+        self.reader
+            .seek(SeekFrom::Start(self.begin + block.start))?;
+        self.reader.read_exact(&mut data)?;
+        self.builder
+            .clone()
+            .data(data)
+            .seed(block_seed(self.seed, sbn))
+            .build()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))
+    }
+
+    /// Produces the next droplet, reading the next source block when
+    /// the current one has sent its share. Returns `None` for an empty
+    /// object.
+    pub fn next_droplet(&mut self) -> io::Result<Option<TaggedDroplet>> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+        if self.cnt_in_block == self.droplets_per_block {
+            self.cnt_in_block = 0;
+            let next = match &self.current {
+                Some((sbn, _)) => (*sbn as usize + 1) % self.cnt_source_blocks(),
+                None => 0,
+            };
+            if next == 0 {
+                self.pass += 1;
+            }
+            // Free the old source block before reading the next one
+            self.current = None;
+            self.current = Some((next as u32, self.source_block(next)?));
+        }
+        if self.current.is_none() {
+            self.current = Some((0, self.source_block(0)?));
+        }
+        let (sbn, enc) = self.current.as_ref().unwrap();
+        let drop = enc.drop_at(self.pass * self.droplets_per_block + self.cnt_in_block);
+        self.cnt_in_block += 1;
+        Ok(Some(TaggedDroplet::new(*sbn, drop)))
+    }
+}
+
+impl<R: Read + Seek> Iterator for StreamEncoder<R> {
+    type Item = io::Result<TaggedDroplet>;
+    fn next(&mut self) -> Option<io::Result<TaggedDroplet>> {
+        self.next_droplet().transpose()
+    }
+}
+
+/// Decoder for droplets of a [`StreamEncoder`], writing every recovered
+/// source block into an `io::Write + io::Seek` sink.
+pub struct StreamDecoder<W> {
+    writer: W,
+    partition: Partition,
+    decoders: HashMap<u32, Decoder>,
+    generator: EdgeGenerator,
+    finished: Vec<bool>,
+    cnt_finished: usize,
+}
+
+impl<W: Write + Seek> StreamDecoder<W> {
+    /// Decodes an object of `len` bytes into `writer`, starting at its
+    /// position 0. The parameters have to match the encoder's.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the parameters are rejected by [`Partition::new`].
+    pub fn new(writer: W, len: u64, blocksize: usize, max_block_symbols: usize) -> Self {
+        let partition = Partition::new(len, blocksize, max_block_symbols)
+            .unwrap_or_else(|err| panic!("invalid partitioning: {}", err));
+        StreamDecoder {
+            writer,
+            partition,
+            decoders: HashMap::new(),
+            generator: EdgeGenerator::default(),
+            finished: vec![false; partition.cnt_source_blocks()],
+            cnt_finished: 0,
+        }
+    }
+
+    /// Expands seeded droplets with `generator` instead of the default
+    /// one. It has to match the encoder's.
+    pub fn with_generator(mut self, generator: EdgeGenerator) -> Self {
+        self.generator = generator;
+        self
+    }
+
+    /// Catches a droplet. Returns true once every source block has been
+    /// written. Droplets of unknown or finished source blocks are
+    /// ignored, malformed droplets are rejected with
+    /// `io::ErrorKind::InvalidData`.
+    pub fn catch(&mut self, drop: TaggedDroplet) -> io::Result<bool> {
+        let sbn = drop.sbn as usize;
+        if sbn >= self.finished.len() || self.finished[sbn] {
+            return Ok(self.is_finished());
+        }
+        let (block, blocksize) = (self.partition.block(sbn), self.partition.blocksize());
+        let generator = self.generator;
+        let dec = self.decoders.entry(drop.sbn).or_insert_with(|| {
+            let len = (block.end - block.start) as usize;
+            Decoder::new(len, blocksize).with_generator(generator)
+        });
+        let result = dec
+            .try_catch(drop.droplet)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        if let CatchResult::Finished(..) = result {
+            let dec = self.decoders.remove(&drop.sbn).unwrap();
+            self.writer.seek(SeekFrom::Start(block.start))?;
+            self.writer.write_all(&dec.into_data())?;
+            self.finished[sbn] = true;
+            self.cnt_finished += 1;
+            if self.is_finished() {
+                self.writer.flush()?;
+            }
+        }
+        Ok(self.is_finished())
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.cnt_finished == self.finished.len()
+    }
+
+    /// Number of source blocks written so far.
+    pub fn cnt_finished_blocks(&self) -> usize {
+        self.cnt_finished
+    }
+
+    pub fn cnt_source_blocks(&self) -> usize {
+        self.finished.len()
+    }
+
+    /// Returns the sink.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
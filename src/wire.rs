@@ -0,0 +1,48 @@
+// Helpers for reading big-endian integers out of slices whose
+// length has already been checked by the caller.
+
+pub fn read_u32(buf: &[u8]) -> u32 {
+    let mut b = [0; 4];
+    b.copy_from_slice(&buf[..4]);
+    u32::from_be_bytes(b)
+}
+
+pub fn read_u64(buf: &[u8]) -> u64 {
+    let mut b = [0; 8];
+    b.copy_from_slice(&buf[..8]);
+    u64::from_be_bytes(b)
+}
+
+// CRC-32 (IEEE 802.3), as used by zlib and gzip.
+pub fn crc32(data: &[u8]) -> u32 {
+    crc32_update(0, data)
+}
+
+// Continues the CRC-32 `crc` of the data before with `data`.
+pub fn crc32_update(crc: u32, data: &[u8]) -> u32 {
+    !data.iter().fold(!crc, |crc, &b| {
+        CRC32_TABLE[((crc ^ b as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
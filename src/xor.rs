@@ -1,40 +1,179 @@
-#![cfg_attr(
-    all(
-        feature = "arm-neon",
-        any(target_arch = "arm", target_arch = "aarch64")
-    ),
-    feature(arm_target_feature),
-    feature(stdsimd)
-)]
-
-// Computing the XOR of two byte slices, `lhs` & `rhs`.
-// `lhs` is mutated in-place with the result
-pub fn xor_bytes(lhs: &mut [u8], rhs: &[u8]) {
-    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
-    {
-        if is_x86_feature_detected!("avx2") {
-            return unsafe { xor_bytes_avx2(lhs, rhs) };
-        } else if is_x86_feature_detected!("sse2") {
-            return unsafe { xor_bytes_sse2(lhs, rhs) };
+//! XOR of byte slices, the workhorse of encoder and decoder.
+//!
+//! [`xor_bytes`] picks the fastest [`Kernel`] the CPU supports. The
+//! detection runs once, later calls reuse its result. [`xor_many`]
+//! combines any number of slices in a single pass over the destination.
+//!
+//! # Example
+//!
+//! ```
+//! use fountaincode::xor::{xor_bytes, Kernel};
+//!
+//! let mut lhs = vec![0b1100; 100];
+//! xor_bytes(&mut lhs, &[0b1010; 100]);
+//! assert_eq!(lhs, vec![0b0110; 100]);
+//!
+//! // The portable kernel gives the same result.
+//! Kernel::Words.xor(&mut lhs, &[0b1010; 100]);
+//! assert_eq!(lhs, vec![0b1100; 100]);
+//! ```
+use std::{
+    cmp,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+#[cfg(target_arch = "aarch64")]
+use std::arch::aarch64 as arch;
+#[cfg(all(feature = "arm-neon", target_arch = "arm"))]
+use std::arch::arm as arch;
+#[cfg(target_arch = "x86")]
+use std::arch::x86 as arch;
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64 as arch;
+
+/// An implementation of the XOR.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kernel {
+    /// 32 bytes at a time with AVX2 on x86 and x86_64
+    Avx2,
+    /// 16 bytes at a time with SSE2 on x86 and x86_64
+    Sse2,
+    /// 16 bytes at a time with NEON on aarch64, and on arm with the
+    /// `arm-neon` feature
+    Neon,
+    /// 8 bytes at a time on any target
+    Words,
+}
+
+// The detected kernel, 0 before the detection ran.
+static DETECTED: AtomicU8 = AtomicU8::new(0);
+
+impl Kernel {
+    const ALL: [Kernel; 4] = [Kernel::Avx2, Kernel::Sse2, Kernel::Neon, Kernel::Words];
+
+    /// The fastest kernel this CPU supports.
+    pub fn detect() -> Kernel {
+        match DETECTED.load(Ordering::Relaxed) {
+            0 => {
+                let kernel = Kernel::ALL
+                    .iter()
+                    .cloned()
+                    .find(|k| k.is_supported())
+                    .unwrap_or(Kernel::Words);
+                DETECTED.store(kernel as u8 + 1, Ordering::Relaxed);
+                kernel
+            }
+            id => Kernel::ALL[id as usize - 1],
+        }
+    }
+
+    /// Whether this CPU can run the kernel.
+    pub fn is_supported(self) -> bool {
+        match self {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Kernel::Avx2 => is_x86_feature_detected!("avx2"),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Kernel::Sse2 => is_x86_feature_detected!("sse2"),
+            #[cfg(target_arch = "aarch64")]
+            Kernel::Neon => std::arch::is_aarch64_feature_detected!("neon"),
+            #[cfg(all(feature = "arm-neon", target_arch = "arm"))]
+            Kernel::Neon => std::arch::is_arm_feature_detected!("neon"),
+            Kernel::Words => true,
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+
+    /// XORs `rhs` into `lhs`, up to the length of the shorter one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this CPU does not support the kernel.
+    pub fn xor(self, lhs: &mut [u8], rhs: &[u8]) {
+        assert!(self.is_supported(), "{:?} is not supported", self);
+        unsafe { self.xor_unchecked(lhs, rhs) }
+    }
+
+    // The CPU has to support the kernel.
+    unsafe fn xor_unchecked(self, lhs: &mut [u8], rhs: &[u8]) {
+        let len = lhs.len().min(rhs.len());
+        let (lhs, rhs) = (&mut lhs[..len], &rhs[..len]);
+        match self {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Kernel::Avx2 => xor_bytes_avx2(lhs, rhs),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Kernel::Sse2 => xor_bytes_sse2(lhs, rhs),
+            #[cfg(any(
+                target_arch = "aarch64",
+                all(feature = "arm-neon", target_arch = "arm")
+            ))]
+            Kernel::Neon => xor_bytes_neon(lhs, rhs),
+            _ => xor_bytes_words(lhs, rhs),
         }
     }
 
-    #[cfg(all(
-        feature = "arm-neon",
-        any(target_arch = "arm", target_arch = "aarch64")
-    ))]
-    {
-        if is_arm_feature_detected!("neon") {
-            return unsafe { xor_bytes_neon(lhs, rhs) };
+    /// XORs all of `srcs` into `dst`, each up to the length of the
+    /// shorter slice, in a single pass over `dst`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this CPU does not support the kernel.
+    pub fn xor_many(self, dst: &mut [u8], srcs: &[&[u8]]) {
+        assert!(self.is_supported(), "{:?} is not supported", self);
+        unsafe { self.xor_many_unchecked(dst, srcs) }
+    }
+
+    // The CPU has to support the kernel.
+    unsafe fn xor_many_unchecked(self, dst: &mut [u8], srcs: &[&[u8]]) {
+        // One pass over the part all slices cover, the longer sources
+        // are added one by one after it.
+        let len = srcs.iter().map(|s| s.len()).fold(dst.len(), cmp::min);
+        let common = &mut dst[..len];
+        match self {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Kernel::Avx2 => xor_many_avx2(common, srcs, 0),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Kernel::Sse2 => xor_many_sse2(common, srcs, 0),
+            #[cfg(any(
+                target_arch = "aarch64",
+                all(feature = "arm-neon", target_arch = "arm")
+            ))]
+            Kernel::Neon => xor_many_neon(common, srcs, 0),
+            _ => xor_many_words(common, srcs, 0),
+        }
+        for src in srcs.iter().filter(|s| s.len() > len) {
+            self.xor_unchecked(&mut dst[len..], &src[len..]);
         }
     }
+}
 
-    xor_bytes_fallback(lhs, rhs);
+/// Computes the XOR of two byte slices, `lhs` & `rhs`, up to the length
+/// of the shorter one. `lhs` is mutated in-place with the result.
+pub fn xor_bytes(lhs: &mut [u8], rhs: &[u8]) {
+    // Safety: the detected kernel is supported.
+    unsafe { Kernel::detect().xor_unchecked(lhs, rhs) }
 }
 
-#[inline(always)]
-fn xor_bytes_fallback(lhs: &mut [u8], rhs: &[u8]) {
-    for (l, r) in lhs.iter_mut().zip(rhs) {
+/// XORs all of `srcs` into `dst`, each up to the length of the shorter
+/// slice, in a single pass over `dst`.
+pub fn xor_many(dst: &mut [u8], srcs: &[&[u8]]) {
+    // Safety: the detected kernel is supported.
+    unsafe { Kernel::detect().xor_many_unchecked(dst, srcs) }
+}
+
+// The slices have the same length in all kernels.
+#[inline]
+fn xor_bytes_words(lhs: &mut [u8], rhs: &[u8]) {
+    let len = lhs.len() - lhs.len() % 8;
+    for i in (0..len).step_by(8) {
+        // Safety: i + 8 <= len
+        unsafe {
+            let l = lhs.as_mut_ptr().add(i) as *mut u64;
+            let r = rhs.as_ptr().add(i) as *const u64;
+            l.write_unaligned(l.read_unaligned() ^ r.read_unaligned());
+        }
+    }
+    for (l, r) in lhs[len..].iter_mut().zip(&rhs[len..]) {
         *l ^= *r;
     }
 }
@@ -42,20 +181,116 @@ fn xor_bytes_fallback(lhs: &mut [u8], rhs: &[u8]) {
 #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
 #[target_feature(enable = "avx2")]
 unsafe fn xor_bytes_avx2(lhs: &mut [u8], rhs: &[u8]) {
-    xor_bytes_fallback(lhs, rhs);
+    let mut lhs_vectors = lhs.chunks_exact_mut(32);
+    let mut rhs_vectors = rhs.chunks_exact(32);
+    for (l, r) in (&mut lhs_vectors).zip(&mut rhs_vectors) {
+        let l = l.as_mut_ptr() as *mut arch::__m256i;
+        let r = r.as_ptr() as *const arch::__m256i;
+        let x = arch::_mm256_xor_si256(arch::_mm256_loadu_si256(l), arch::_mm256_loadu_si256(r));
+        arch::_mm256_storeu_si256(l, x);
+    }
+    xor_bytes_sse2(lhs_vectors.into_remainder(), rhs_vectors.remainder());
 }
 
 #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
 #[target_feature(enable = "sse2")]
 unsafe fn xor_bytes_sse2(lhs: &mut [u8], rhs: &[u8]) {
-    xor_bytes_fallback(lhs, rhs);
+    let mut lhs_vectors = lhs.chunks_exact_mut(16);
+    let mut rhs_vectors = rhs.chunks_exact(16);
+    for (l, r) in (&mut lhs_vectors).zip(&mut rhs_vectors) {
+        let l = l.as_mut_ptr() as *mut arch::__m128i;
+        let r = r.as_ptr() as *const arch::__m128i;
+        let x = arch::_mm_xor_si128(arch::_mm_loadu_si128(l), arch::_mm_loadu_si128(r));
+        arch::_mm_storeu_si128(l, x);
+    }
+    xor_bytes_words(lhs_vectors.into_remainder(), rhs_vectors.remainder());
 }
 
-#[cfg(all(
-    feature = "arm-neon",
-    any(target_arch = "arm", target_arch = "aarch64")
+#[cfg(any(
+    target_arch = "aarch64",
+    all(feature = "arm-neon", target_arch = "arm")
 ))]
 #[target_feature(enable = "neon")]
 unsafe fn xor_bytes_neon(lhs: &mut [u8], rhs: &[u8]) {
-    xor_bytes_fallback(lhs, rhs);
+    let mut lhs_vectors = lhs.chunks_exact_mut(16);
+    let mut rhs_vectors = rhs.chunks_exact(16);
+    for (l, r) in (&mut lhs_vectors).zip(&mut rhs_vectors) {
+        let l = l.as_mut_ptr();
+        let x = arch::veorq_u8(arch::vld1q_u8(l), arch::vld1q_u8(r.as_ptr()));
+        arch::vst1q_u8(l, x);
+    }
+    xor_bytes_words(lhs_vectors.into_remainder(), rhs_vectors.remainder());
+}
+
+// The `xor_many_*` kernels combine the sources into `dst` from byte
+// `begin` on. No source may be shorter than `dst`.
+
+fn xor_many_words(dst: &mut [u8], srcs: &[&[u8]], begin: usize) {
+    let len = begin + (dst.len() - begin) / 8 * 8;
+    for i in (begin..len).step_by(8) {
+        // Safety: i + 8 <= len and every source is at least as long
+        unsafe {
+            let d = dst.as_mut_ptr().add(i) as *mut u64;
+            let mut x = d.read_unaligned();
+            for src in srcs {
+                x ^= (src.as_ptr().add(i) as *const u64).read_unaligned();
+            }
+            d.write_unaligned(x);
+        }
+    }
+    for i in len..dst.len() {
+        for src in srcs {
+            dst[i] ^= src[i];
+        }
+    }
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "avx2")]
+unsafe fn xor_many_avx2(dst: &mut [u8], srcs: &[&[u8]], begin: usize) {
+    let len = begin + (dst.len() - begin) / 32 * 32;
+    for i in (begin..len).step_by(32) {
+        let d = dst.as_mut_ptr().add(i) as *mut arch::__m256i;
+        let mut x = arch::_mm256_loadu_si256(d);
+        for src in srcs {
+            let s = src.as_ptr().add(i) as *const arch::__m256i;
+            x = arch::_mm256_xor_si256(x, arch::_mm256_loadu_si256(s));
+        }
+        arch::_mm256_storeu_si256(d, x);
+    }
+    xor_many_sse2(dst, srcs, len);
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "sse2")]
+unsafe fn xor_many_sse2(dst: &mut [u8], srcs: &[&[u8]], begin: usize) {
+    let len = begin + (dst.len() - begin) / 16 * 16;
+    for i in (begin..len).step_by(16) {
+        let d = dst.as_mut_ptr().add(i) as *mut arch::__m128i;
+        let mut x = arch::_mm_loadu_si128(d);
+        for src in srcs {
+            let s = src.as_ptr().add(i) as *const arch::__m128i;
+            x = arch::_mm_xor_si128(x, arch::_mm_loadu_si128(s));
+        }
+        arch::_mm_storeu_si128(d, x);
+    }
+    xor_many_words(dst, srcs, len);
+}
+
+#[cfg(any(
+    target_arch = "aarch64",
+    all(feature = "arm-neon", target_arch = "arm")
+))]
+#[target_feature(enable = "neon")]
+unsafe fn xor_many_neon(dst: &mut [u8], srcs: &[&[u8]], begin: usize) {
+    let len = begin + (dst.len() - begin) / 16 * 16;
+    for i in (begin..len).step_by(16) {
+        let d = dst.as_mut_ptr().add(i);
+        let mut x = arch::vld1q_u8(d);
+        for src in srcs {
+            x = arch::veorq_u8(x, arch::vld1q_u8(src.as_ptr().add(i)));
+        }
+        arch::vst1q_u8(d, x);
+    }
+    xor_many_words(dst, srcs, len);
 }
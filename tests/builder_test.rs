@@ -0,0 +1,124 @@
+use fountaincode::{
+    builder::{default_c, ConfigError, DecoderBuilder, EncoderBuilder},
+    decoder::{CatchResult, DecoderType},
+    encoder::EncoderType,
+    header::{CodeVariant, EdgeGenerator},
+    soliton::SolitonError,
+};
+
+#[test]
+fn build_and_decode() {
+    let msg: Vec<u8> = (0..5_000).map(|i| (i * 3) as u8).collect();
+    for &blocksize in &[1, 7, 100, 5_000, 10_000] {
+        let mut enc = EncoderBuilder::new(&msg[..])
+            .blocksize(blocksize)
+            .encoder_type(EncoderType::Systematic)
+            .generator(EdgeGenerator::SplitMix64)
+            .build()
+            .unwrap();
+        let mut dec = DecoderBuilder::new(msg.len())
+            .blocksize(blocksize)
+            .decoder_type(DecoderType::Inactivation)
+            .generator(EdgeGenerator::SplitMix64)
+            .build()
+            .unwrap();
+        loop {
+            if let CatchResult::Finished(data, _) = dec.catch(enc.drop()) {
+                assert_eq!(data, &msg[..]);
+                break;
+            }
+        }
+    }
+}
+
+#[test]
+fn defaults() {
+    // c follows the number of blocks
+    for &(len, expected) in &[(10_000, 0.2), (200_000, 0.05), (2_000_000, 0.03)] {
+        let enc = EncoderBuilder::new(vec![1; len]).build().unwrap();
+        match enc.header().variant() {
+            CodeVariant::RobustSoliton { c, delta, .. } => {
+                assert_eq!((c, delta), (expected, 0.5));
+                assert_eq!(default_c(len.div_ceil(1024)), expected);
+            }
+            CodeVariant::IdealSoliton => panic!("robust soliton expected"),
+        }
+        assert_eq!(enc.header().blocksize(), 1024);
+    }
+    let enc = EncoderBuilder::new(vec![1; 10_000])
+        .c(0.1)
+        .delta(0.05)
+        .build()
+        .unwrap();
+    match enc.header().variant() {
+        CodeVariant::RobustSoliton { c, delta, .. } => assert_eq!((c, delta), (0.1, 0.05)),
+        CodeVariant::IdealSoliton => panic!("robust soliton expected"),
+    }
+
+    let enc = EncoderBuilder::new(vec![1; 10_000])
+        .ideal()
+        .seed(5)
+        .build()
+        .unwrap();
+    assert_eq!(enc.header().variant(), CodeVariant::IdealSoliton);
+    assert_eq!(enc.seed(), 5);
+}
+
+#[test]
+fn invalid_configurations() {
+    assert_eq!(
+        EncoderBuilder::new(Vec::new()).build().err(),
+        Some(ConfigError::EmptyMessage)
+    );
+    assert_eq!(
+        EncoderBuilder::new(vec![1; 100]).blocksize(0).build().err(),
+        Some(ConfigError::ZeroBlocksize)
+    );
+    assert_eq!(
+        EncoderBuilder::new(vec![1; 100]).c(-1.0).build().err(),
+        Some(ConfigError::Soliton(SolitonError::InvalidC(-1.0)))
+    );
+    assert_eq!(
+        EncoderBuilder::new(vec![1; 100]).delta(1.0).build().err(),
+        Some(ConfigError::Soliton(SolitonError::InvalidDelta(1.0)))
+    );
+    assert_eq!(
+        EncoderBuilder::new(vec![1; 100])
+            .blocksize(10)
+            .spike(11)
+            .build()
+            .err(),
+        Some(ConfigError::Soliton(SolitonError::InvalidSpike {
+            spike: 11,
+            k: 10
+        }))
+    );
+    // Headers, droplets and snapshots carry the block size as a u32
+    let too_large = u32::MAX as usize + 1;
+    assert_eq!(
+        EncoderBuilder::new(vec![1; 100])
+            .blocksize(too_large)
+            .build()
+            .err(),
+        Some(ConfigError::BlocksizeTooLarge(too_large))
+    );
+    assert_eq!(
+        DecoderBuilder::new(100).blocksize(too_large).build().err(),
+        Some(ConfigError::BlocksizeTooLarge(too_large))
+    );
+    // The Soliton parameters do not matter for the Ideal Soliton
+    assert!(EncoderBuilder::new(vec![1; 100])
+        .c(-1.0)
+        .ideal()
+        .build()
+        .is_ok());
+
+    assert_eq!(
+        DecoderBuilder::new(0).build().err().map(|e| e.to_string()),
+        Some("the message is empty".to_string())
+    );
+    assert!(matches!(
+        DecoderBuilder::new(100).blocksize(0).build(),
+        Err(ConfigError::ZeroBlocksize)
+    ));
+}
@@ -3,14 +3,14 @@ use fountaincode::{
     encoder::{Encoder, EncoderType},
 };
 use proptest::prelude::*;
-use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use rand::{distributions::Alphanumeric, rngs::StdRng, Rng, SeedableRng};
 use stopwatch::Stopwatch;
 
 proptest! {
     #![proptest_config(ProptestConfig::with_cases(10000))]
     #[test]
-    fn compare_prop_test(total_len in 1024_usize..8192, chunk_len in 8_usize..512) {
-        let s: String = thread_rng()
+    fn compare_prop_test(total_len in 1024_usize..8192, chunk_len in 8_usize..512, seed in any::<u64>()) {
+        let s: String = StdRng::seed_from_u64(seed)
             .sample_iter(Alphanumeric)
             .take(total_len)
             .collect();
@@ -19,11 +19,11 @@ proptest! {
 
         let mut dec = Decoder::new(len, chunk_len );
 
-        let mut renc = Encoder::robust(buf.clone(), chunk_len , EncoderType::Systematic, 0.2, None, 0.05);
+        let mut renc = Encoder::robust(buf.clone(), chunk_len , EncoderType::Systematic, 0.2, None, 0.05).with_seed(seed);
         let mut sw = Stopwatch::start_new();
         let res1 = robust_run(&mut renc, &mut dec);
         let t1 = sw.elapsed();
-        let mut ienc = Encoder::ideal(buf.clone(), chunk_len , EncoderType::Systematic);
+        let mut ienc = Encoder::ideal(buf.clone(), chunk_len , EncoderType::Systematic).with_seed(seed);
         sw.restart();
         let res2 = ideal_run(&mut ienc, &mut dec);
         let t2 = sw.elapsed();
@@ -38,8 +38,8 @@ proptest! {
 proptest! {
     #![proptest_config(ProptestConfig::with_cases(10000))]
     #[test]
-    fn compare_prop_lossy_test(total_len in 1024_usize..8192, chunk_len in 8_usize..512) {
-        let s: String = thread_rng()
+    fn compare_prop_lossy_test(total_len in 1024_usize..8192, chunk_len in 8_usize..512, seed in any::<u64>()) {
+        let s: String = StdRng::seed_from_u64(seed)
             .sample_iter(Alphanumeric)
             .take(total_len )
             .collect();
@@ -51,13 +51,13 @@ proptest! {
         let mut res2: Vec<u8> = vec![];
 
         for loss in &[0.1, 0.3, 0.5, 0.9] {
-            let mut renc = Encoder::robust(buf.clone(), chunk_len , EncoderType::Systematic, 0.2, None, 0.05);
+            let mut renc = Encoder::robust(buf.clone(), chunk_len , EncoderType::Systematic, 0.2, None, 0.05).with_seed(seed);
             let mut sw = Stopwatch::start_new();
-            res1 = robust_run_lossy(&mut renc, &mut dec, *loss);
+            res1 = robust_run_lossy(&mut renc, &mut dec, *loss, seed);
             let t1 = sw.elapsed();
-            let mut ienc = Encoder::ideal(buf.clone(), chunk_len, EncoderType::Systematic);
+            let mut ienc = Encoder::ideal(buf.clone(), chunk_len, EncoderType::Systematic).with_seed(seed);
             sw.restart();
-            res2 = ideal_run_lossy(&mut ienc, &mut dec, *loss);
+            res2 = ideal_run_lossy(&mut ienc, &mut dec, *loss, seed);
             let t2 = sw.elapsed();
             println!("total_len: {:?}, chunk_len: {:?}, loss: {:?}, robust_time: {:?}, ideal_time: {:?}",
                 total_len, chunk_len, loss, t1, t2);
@@ -77,7 +77,7 @@ fn robust_run(enc: &mut Encoder, dec: &mut Decoder) -> Vec<u8> {
             }
             CatchResult::Finished(data, stats) => {
                 println!("robust_overhead: {:?}", stats.overhead);
-                break data;
+                break data.to_vec();
             }
         }
     }
@@ -92,14 +92,14 @@ fn ideal_run(enc: &mut Encoder, dec: &mut Decoder) -> Vec<u8> {
             }
             CatchResult::Finished(data, stats) => {
                 println!("ideal_overhead: {:?}", stats.overhead);
-                break data;
+                break data.to_vec();
             }
         }
     }
 }
 
-fn robust_run_lossy(enc: &mut Encoder, dec: &mut Decoder, loss: f32) -> Vec<u8> {
-    let mut loss_rng = thread_rng();
+fn robust_run_lossy(enc: &mut Encoder, dec: &mut Decoder, loss: f32, seed: u64) -> Vec<u8> {
+    let mut loss_rng = StdRng::seed_from_u64(seed);
 
     loop {
         if loss_rng.gen::<f32>() > loss {
@@ -110,15 +110,15 @@ fn robust_run_lossy(enc: &mut Encoder, dec: &mut Decoder, loss: f32) -> Vec<u8>
                 }
                 CatchResult::Finished(data, stats) => {
                     println!("robust_overhead: {:?}", stats.overhead);
-                    break data;
+                    break data.to_vec();
                 }
             }
         }
     }
 }
 
-fn ideal_run_lossy(enc: &mut Encoder, dec: &mut Decoder, loss: f32) -> Vec<u8> {
-    let mut loss_rng = thread_rng();
+fn ideal_run_lossy(enc: &mut Encoder, dec: &mut Decoder, loss: f32, seed: u64) -> Vec<u8> {
+    let mut loss_rng = StdRng::seed_from_u64(seed);
     loop {
         if loss_rng.gen::<f32>() > loss {
             let drop = enc.drop();
@@ -128,7 +128,7 @@ fn ideal_run_lossy(enc: &mut Encoder, dec: &mut Decoder, loss: f32) -> Vec<u8> {
                 }
                 CatchResult::Finished(data, stats) => {
                     println!("ideal_overhead: {:?}", stats.overhead);
-                    break data;
+                    break data.to_vec();
                 }
             }
         }
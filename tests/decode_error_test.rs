@@ -0,0 +1,83 @@
+use fountaincode::{
+    decoder::{CatchResult, DecodeError, Decoder},
+    droplet::{DropType, Droplet},
+    encoder::{Encoder, EncoderType},
+};
+
+#[test]
+fn rejects_wrong_payload_length() {
+    let mut dec = Decoder::new(1_000, 100);
+    for &len in &[0, 99, 101] {
+        let drop = Droplet::new(DropType::Edges(0), vec![0; len]);
+        assert_eq!(
+            dec.try_catch(drop).unwrap_err(),
+            DecodeError::LengthMismatch {
+                expected: 100,
+                actual: len
+            }
+        );
+    }
+}
+
+#[test]
+fn rejects_index_out_of_range() {
+    let mut dec = Decoder::new(1_000, 100);
+    let drop = Droplet::new(DropType::Edges(10), vec![0; 100]);
+    assert_eq!(
+        dec.try_catch(drop).unwrap_err(),
+        DecodeError::IndexOutOfRange {
+            idx: 10,
+            cnt_blocks: 10
+        }
+    );
+}
+
+#[test]
+fn rejects_zero_degree() {
+    let mut dec = Decoder::new(1_000, 100);
+    let drop = Droplet::new(DropType::Seeded(1, 0), vec![0; 100]);
+    assert_eq!(dec.try_catch(drop).unwrap_err(), DecodeError::ZeroDegree);
+}
+
+#[test]
+fn rejects_degree_above_block_count() {
+    let mut dec = Decoder::new(1_000, 100);
+    let drop = Droplet::new(DropType::Seeded(1, 11), vec![0; 100]);
+    assert_eq!(
+        dec.try_catch(drop).unwrap_err(),
+        DecodeError::DegreeTooLarge {
+            degree: 11,
+            cnt_blocks: 10
+        }
+    );
+    let drop = Droplet::new(DropType::Seeded(1, 10), vec![0; 100]);
+    assert!(dec.try_catch(drop).is_ok());
+}
+
+#[test]
+fn rejected_droplets_leave_the_decoder_unchanged() {
+    let msg: Vec<u8> = (0..1_000).map(|i| i as u8).collect();
+    let mut enc = Encoder::ideal(msg.clone(), 100, EncoderType::Systematic);
+    let mut dec = Decoder::new(msg.len(), 100);
+
+    assert!(dec
+        .try_catch(Droplet::new(DropType::Edges(3), vec![1; 7]))
+        .is_err());
+    for _ in 0..10 {
+        match dec.try_catch(enc.drop()).unwrap() {
+            CatchResult::Missing(_) => {}
+            CatchResult::Finished(data, stats) => {
+                assert_eq!(data, &msg[..]);
+                assert_eq!(stats.cnt_droplets, 10);
+            }
+        }
+    }
+    assert!(dec.is_finished());
+}
+
+#[test]
+#[should_panic(expected = "malformed droplet")]
+fn catch_panics_on_malformed_droplets() {
+    let mut dec = Decoder::new(1_000, 100);
+    dec.catch(Droplet::new(DropType::Edges(10), vec![0; 100]));
+}
@@ -0,0 +1,84 @@
+use fountaincode::{
+    decoder::{CatchResult, Decoder},
+    distribution::{DegreeDistribution, DistributionError, Tabulated},
+    encoder::{Encoder, EncoderType},
+    soliton::Soliton,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+#[test]
+fn tabulated_distribution() {
+    // Trailing zeros do not count towards the maximum degree.
+    let dist = Tabulated::new(vec![1.0, 2.0, 0.0, 1.0, 0.0]).unwrap();
+    assert_eq!(dist.max_degree(), 4);
+    assert_eq!(dist.pmf(0), 0.0);
+    assert_eq!(dist.pmf(1), 0.25);
+    assert_eq!(dist.pmf(2), 0.5);
+    assert_eq!(dist.pmf(3), 0.0);
+    assert_eq!(dist.pmf(4), 0.25);
+    assert_eq!(dist.pmf(5), 0.0);
+
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut counts = [0_i32; 5];
+    for _ in 0..100_000 {
+        counts[dist.sample(&mut rng)] += 1;
+    }
+    assert_eq!(counts[0], 0);
+    assert_eq!(counts[3], 0);
+    for &(degree, expected) in &[(1, 25_000), (2, 50_000), (4, 25_000)] {
+        assert!((counts[degree] - expected).abs() < 1000);
+    }
+}
+
+#[test]
+fn tabulated_validation() {
+    assert_eq!(
+        Tabulated::new(vec![0.5, -0.1]).unwrap_err(),
+        DistributionError::InvalidProbability {
+            degree: 2,
+            probability: -0.1
+        }
+    );
+    assert!(matches!(
+        Tabulated::new(vec![f32::NAN]),
+        Err(DistributionError::InvalidProbability { degree: 1, .. })
+    ));
+    assert_eq!(
+        Tabulated::new(vec![0.0, 0.0]).unwrap_err(),
+        DistributionError::ZeroTotal
+    );
+    assert_eq!(
+        Tabulated::new(Vec::new()).unwrap_err(),
+        DistributionError::ZeroTotal
+    );
+}
+
+#[test]
+fn soliton_max_degree() {
+    assert_eq!(Soliton::ideal(100).max_degree(), 100);
+    assert_eq!(Soliton::robust(100, 0.2, None, 0.05).max_degree(), 100);
+}
+
+#[test]
+fn tabulated_enc_dec() {
+    let msg: Vec<u8> = (0..10_000).map(|i| (i % 241) as u8).collect();
+    // A truncated Ideal Soliton distribution.
+    let probabilities = (1..=20)
+        .map(|d: usize| {
+            if d == 1 {
+                0.05
+            } else {
+                1.0 / (d * (d - 1)) as f32
+            }
+        })
+        .collect();
+    let dist = Tabulated::new(probabilities).unwrap();
+    let enc = Encoder::with_distribution(msg.clone(), 100, EncoderType::Random, dist);
+    let mut dec = Decoder::new(msg.len(), 100);
+    for drop in enc {
+        if let CatchResult::Finished(data, _) = dec.catch(drop) {
+            assert_eq!(data, msg);
+            return;
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use fountaincode::{
+    decoder::{CatchResult, Decoder},
+    droplet::{DropType, Droplet, WireError, WIRE_VERSION},
+    encoder::{Encoder, EncoderType},
+};
+use proptest::prelude::*;
+
+#[test]
+fn wire_seeded_roundtrip() {
+    let drop = Droplet::new(
+        DropType::Seeded(0xdead_beef_0123_4567, 17),
+        vec![1, 2, 3, 4],
+    );
+    let bytes = drop.to_bytes();
+
+    assert_eq!(bytes.len(), drop.encoded_len());
+    assert_eq!(&bytes[..4], &[b'F', b'C', WIRE_VERSION, 0]);
+    assert_eq!(&bytes[4..12], &0xdead_beef_0123_4567_u64.to_be_bytes());
+    assert_eq!(&bytes[12..16], &17_u32.to_be_bytes());
+    assert_eq!(&bytes[16..20], &4_u32.to_be_bytes());
+    assert_eq!(Droplet::from_bytes(&bytes).unwrap(), drop);
+}
+
+#[test]
+fn wire_edges_roundtrip() {
+    let drop = Droplet::new(DropType::Edges(42), vec![9; 64]);
+    let bytes = drop.to_bytes();
+
+    assert_eq!(bytes.len(), 12 + 64);
+    assert_eq!(&bytes[..4], &[b'F', b'C', WIRE_VERSION, 1]);
+    assert_eq!(&bytes[4..8], &42_u32.to_be_bytes());
+    assert_eq!(Droplet::from_bytes(&bytes).unwrap(), drop);
+}
+
+#[test]
+fn wire_write_into() {
+    let drop = Droplet::new(DropType::Edges(3), vec![7; 10]);
+    let mut buf = [0; 64];
+    let n = drop.write_into(&mut buf).unwrap();
+    assert_eq!(n, drop.encoded_len());
+    assert_eq!(Droplet::from_bytes(&buf[..n]).unwrap(), drop);
+
+    let mut small = [0; 12];
+    assert_eq!(
+        drop.write_into(&mut small),
+        Err(WireError::BufferTooSmall {
+            needed: 22,
+            available: 12
+        })
+    );
+}
+
+#[test]
+fn wire_malformed_input() {
+    let bytes = Droplet::new(DropType::Seeded(1, 2), vec![5; 8]).to_bytes();
+
+    for len in 0..bytes.len() {
+        match Droplet::from_bytes(&bytes[..len]) {
+            Err(WireError::Truncated { available, .. }) => assert_eq!(available, len),
+            other => panic!("expected truncation error, got {:?}", other),
+        }
+    }
+
+    let mut bad = bytes.clone();
+    bad[0] = b'X';
+    assert_eq!(Droplet::from_bytes(&bad), Err(WireError::BadMagic));
+
+    let mut bad = bytes.clone();
+    bad[2] = WIRE_VERSION + 1;
+    assert_eq!(
+        Droplet::from_bytes(&bad),
+        Err(WireError::UnsupportedVersion(WIRE_VERSION + 1))
+    );
+
+    let mut bad = bytes.clone();
+    bad[3] = 7;
+    assert_eq!(
+        Droplet::from_bytes(&bad),
+        Err(WireError::UnknownDropType(7))
+    );
+
+    let mut bad = bytes;
+    bad.push(0);
+    assert_eq!(Droplet::from_bytes(&bad), Err(WireError::TrailingBytes(1)));
+}
+
+#[test]
+fn wire_enc_dec() {
+    let msg: Vec<u8> = (0..4096).map(|i| (i % 251) as u8).collect();
+    let mut enc = Encoder::robust(msg.clone(), 100, EncoderType::Systematic, 0.2, None, 0.05);
+    let mut dec = Decoder::new(msg.len(), 100);
+
+    loop {
+        let bytes = enc.drop().to_bytes();
+        let drop = Droplet::from_bytes(&bytes).unwrap();
+        if let CatchResult::Finished(data, _stats) = dec.catch(drop) {
+            assert_eq!(data, msg);
+            break;
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn wire_prop_roundtrip(seed in any::<u64>(), degree in 0_usize..1 << 20, data in prop::collection::vec(any::<u8>(), 0..1500)) {
+        let drop = Droplet::new(DropType::Seeded(seed, degree), data);
+        prop_assert_eq!(Droplet::from_bytes(&drop.to_bytes()), Ok(drop));
+    }
+
+    #[test]
+    fn wire_prop_garbage(bytes in prop::collection::vec(any::<u8>(), 0..64)) {
+        // Arbitrary input must never panic, only return an error.
+        let _ = Droplet::from_bytes(&bytes);
+    }
+}
@@ -0,0 +1,79 @@
+use fountaincode::{
+    decoder::{CatchResult, Decoder, DecoderType},
+    droplet::{DropType, Droplet},
+    encoder::{Encoder, EncoderType},
+};
+
+#[test]
+fn duplicates_are_counted_and_ignored() {
+    let msg: Vec<u8> = (0..1_000).map(|i| i as u8).collect();
+    let mut enc = Encoder::ideal(msg.clone(), 100, EncoderType::Random);
+    let mut dec = Decoder::with_type(msg.len(), 100, DecoderType::Inactivation);
+
+    loop {
+        let drop = enc.drop();
+        dec.catch(drop.clone());
+        if let CatchResult::Finished(data, stats) = dec.catch(drop) {
+            assert_eq!(data, &msg[..]);
+            assert_eq!(stats.cnt_duplicates * 2, stats.cnt_droplets);
+            let cnt_useful = stats.cnt_droplets - stats.cnt_duplicates - stats.cnt_redundant;
+            assert_eq!(stats.overhead, cnt_useful as f32 * 10.0);
+            break;
+        }
+    }
+}
+
+#[test]
+fn droplets_of_known_blocks_are_redundant() {
+    let msg: Vec<u8> = (0..1_000).map(|i| i as u8).collect();
+    let mut enc = Encoder::ideal(msg.clone(), 100, EncoderType::Systematic);
+    let mut dec = Decoder::new(msg.len(), 100);
+    for _ in 0..10 {
+        dec.catch(enc.drop());
+    }
+    assert!(dec.is_finished());
+
+    let mut random = Encoder::ideal(msg, 100, EncoderType::Random);
+    for i in 1..=5 {
+        match dec.catch(random.drop()) {
+            CatchResult::Finished(_, stats) => {
+                assert_eq!(stats.cnt_redundant, i);
+                assert_eq!(stats.cnt_duplicates, 0);
+                assert_eq!(stats.overhead, 100.0);
+            }
+            CatchResult::Missing(_) => panic!("decoder was finished"),
+        }
+    }
+    match dec.catch(enc.drop()) {
+        CatchResult::Finished(_, stats) => assert_eq!(stats.cnt_duplicates, 1),
+        CatchResult::Missing(_) => panic!("decoder was finished"),
+    }
+}
+
+#[test]
+fn only_recent_seeded_droplets_are_remembered() {
+    // Degree two droplets never start peeling, so the decoder keeps
+    // taking them. It remembers the latest 2k = 200 of them.
+    let mut dec = Decoder::new(10_000, 100);
+    let drop = |seed| Droplet::new(DropType::Seeded(seed, 2), vec![0; 100]);
+    let cnt_duplicates = |dec: &mut Decoder, seed| match dec.catch(drop(seed)) {
+        CatchResult::Missing(stats) => stats.cnt_duplicates,
+        CatchResult::Finished(..) => panic!("degree two droplets cannot finish"),
+    };
+    for seed in 0..=200 {
+        assert_eq!(cnt_duplicates(&mut dec, seed), 0);
+    }
+    assert_eq!(cnt_duplicates(&mut dec, 200), 1);
+    // Seed 0 was forgotten, and taking it again drops seed 1
+    assert_eq!(cnt_duplicates(&mut dec, 0), 1);
+
+    let mut buf = Vec::new();
+    dec.save(&mut buf).unwrap();
+    let mut restored = Decoder::restore(&buf[..]).unwrap();
+    // The restored decoder remembers the same droplets
+    assert_eq!(cnt_duplicates(&mut restored, 0), 2);
+    assert_eq!(cnt_duplicates(&mut restored, 199), 3);
+    assert_eq!(cnt_duplicates(&mut restored, 1), 3);
+    // and forgets them in the same order
+    assert_eq!(cnt_duplicates(&mut restored, 2), 3);
+}
@@ -0,0 +1,139 @@
+use fountaincode::{
+    decoder::{CatchResult, Decoder},
+    encoder::{Encoder, EncoderType},
+    header::{CodeVariant, EdgeGenerator, HeaderError, SessionHeader, HEADER_VERSION},
+    soliton::SolitonError,
+};
+
+fn transfer(mut enc: Encoder, msg: &[u8]) {
+    // Only the serialized header crosses the "channel" besides droplets.
+    let header = SessionHeader::from_bytes(&enc.header().to_bytes()).unwrap();
+    let mut dec = Decoder::from_header(&header);
+
+    loop {
+        if let CatchResult::Finished(data, _stats) = dec.catch(enc.drop()) {
+            assert_eq!(data, msg);
+            return;
+        }
+    }
+}
+
+#[test]
+fn header_robust_roundtrip() {
+    let msg: Vec<u8> = (0..3000).map(|i| (i % 199) as u8).collect();
+    let enc = Encoder::robust(msg.clone(), 128, EncoderType::Random, 0.2, Some(7), 0.05);
+    let header = enc.header();
+
+    assert_eq!(header.len(), 3000);
+    assert_eq!(header.blocksize(), 128);
+    assert_eq!(header.cnt_blocks(), 24);
+    assert_eq!(header.generator(), EdgeGenerator::SplitMix64Distinct);
+    assert_eq!(
+        header.variant(),
+        CodeVariant::RobustSoliton {
+            c: 0.2,
+            delta: 0.05,
+            spike: 7
+        }
+    );
+    assert_eq!(SessionHeader::from_bytes(&header.to_bytes()), Ok(header));
+
+    transfer(enc, &msg);
+}
+
+#[test]
+fn header_ideal_roundtrip() {
+    let msg: Vec<u8> = (0..1001).map(|i| (i % 13) as u8).collect();
+    let enc = Encoder::ideal(msg.clone(), 100, EncoderType::Systematic);
+    let header = enc.header();
+
+    assert_eq!(header.variant(), CodeVariant::IdealSoliton);
+    assert_eq!(header.encoded_len(), 17);
+    assert_eq!(SessionHeader::from_bytes(&header.to_bytes()), Ok(header));
+
+    transfer(enc, &msg);
+}
+
+#[test]
+fn header_malformed_input() {
+    let enc = Encoder::robust(vec![1; 500], 50, EncoderType::Random, 0.2, None, 0.05);
+    let bytes = enc.header().to_bytes();
+
+    for len in 0..bytes.len() {
+        match SessionHeader::from_bytes(&bytes[..len]) {
+            Err(HeaderError::Truncated { available, .. }) => assert_eq!(available, len),
+            other => panic!("expected truncation error, got {:?}", other),
+        }
+    }
+
+    let mut bad = bytes.clone();
+    bad[1] = 0;
+    assert_eq!(SessionHeader::from_bytes(&bad), Err(HeaderError::BadMagic));
+
+    let mut bad = bytes.clone();
+    bad[2] = HEADER_VERSION + 1;
+    assert_eq!(
+        SessionHeader::from_bytes(&bad),
+        Err(HeaderError::UnsupportedVersion(HEADER_VERSION + 1))
+    );
+
+    let mut bad = bytes.clone();
+    bad[3] = 9;
+    assert_eq!(
+        SessionHeader::from_bytes(&bad),
+        Err(HeaderError::UnknownVariant(9))
+    );
+
+    let mut bad = bytes.clone();
+    bad[4..12].copy_from_slice(&[0; 8]);
+    assert_eq!(
+        SessionHeader::from_bytes(&bad),
+        Err(HeaderError::EmptyObject)
+    );
+
+    let mut bad = bytes.clone();
+    bad[12..16].copy_from_slice(&[0; 4]);
+    assert_eq!(
+        SessionHeader::from_bytes(&bad),
+        Err(HeaderError::ZeroBlocksize)
+    );
+
+    let mut bad = bytes.clone();
+    bad[16] = 200;
+    assert_eq!(
+        SessionHeader::from_bytes(&bad),
+        Err(HeaderError::UnknownGenerator(200))
+    );
+
+    // 500 bytes in blocks of 50
+    for &(field, value, err) in &[
+        (17, 0_f32.to_bits(), SolitonError::InvalidC(0.0)),
+        (21, 1_f32.to_bits(), SolitonError::InvalidDelta(1.0)),
+        (25, 0, SolitonError::InvalidSpike { spike: 0, k: 10 }),
+        (25, 11, SolitonError::InvalidSpike { spike: 11, k: 10 }),
+    ] {
+        let mut bad = bytes.clone();
+        bad[field..field + 4].copy_from_slice(&value.to_be_bytes());
+        assert_eq!(
+            SessionHeader::from_bytes(&bad),
+            Err(HeaderError::Soliton(err))
+        );
+    }
+    for &field in &[17, 21] {
+        let mut bad = bytes.clone();
+        bad[field..field + 4].copy_from_slice(&f32::NAN.to_bits().to_be_bytes());
+        assert!(matches!(
+            SessionHeader::from_bytes(&bad),
+            Err(HeaderError::Soliton(
+                SolitonError::InvalidC(_) | SolitonError::InvalidDelta(_)
+            ))
+        ));
+    }
+
+    let mut bad = bytes;
+    bad.extend_from_slice(&[0, 0]);
+    assert_eq!(
+        SessionHeader::from_bytes(&bad),
+        Err(HeaderError::TrailingBytes(2))
+    );
+}
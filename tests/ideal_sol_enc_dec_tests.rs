@@ -84,3 +84,17 @@ fn ideal_combinations_encode_decode_with_loss_begin_with_systematic() {
         }
     }
 }
+
+#[test]
+fn ideal_data_and_into_data() {
+    let msg: Vec<u8> = (0..1_000).map(|i| i as u8).collect();
+    let mut enc = Encoder::ideal(msg.clone(), 64, EncoderType::Systematic);
+    let mut dec = Decoder::new(msg.len(), 64);
+    assert_eq!(dec.data(), None);
+
+    while !dec.is_finished() {
+        dec.catch(enc.drop());
+    }
+    assert_eq!(dec.data(), Some(&msg[..]));
+    assert_eq!(dec.into_data(), msg);
+}
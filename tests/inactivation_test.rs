@@ -0,0 +1,122 @@
+use fountaincode::{
+    decoder::{CatchResult, Decoder, DecoderType},
+    droplet::{DropType, Droplet},
+    encoder::{Encoder, EncoderType},
+    header::EdgeGenerator,
+};
+use rand::{thread_rng, Rng};
+use std::collections::BTreeSet;
+
+// Finds a seed whose droplet covers exactly `edges`.
+fn seed_for(edges: &[usize], k: usize) -> u64 {
+    let wanted: BTreeSet<usize> = edges.iter().cloned().collect();
+    (0..)
+        .find(|&seed| {
+            let sample = EdgeGenerator::default().sample(seed, k, edges.len());
+            sample.iter().cloned().collect::<BTreeSet<usize>>() == wanted
+        })
+        .unwrap()
+}
+
+fn xor_blocks(msg: &[u8], edges: &[usize], blocksize: usize) -> Vec<u8> {
+    let mut r = vec![0; blocksize];
+    for &e in edges {
+        for (i, b) in msg[e * blocksize..(e + 1) * blocksize].iter().enumerate() {
+            r[i] ^= b;
+        }
+    }
+    r
+}
+
+#[test]
+fn inactivation_solves_stalled_system() {
+    // No droplet has degree one, so peeling never starts, but the three
+    // equations are linearly independent.
+    let msg: Vec<u8> = (0..12).collect();
+    let sets: [&[usize]; 3] = [&[0, 1], &[1, 2], &[0, 1, 2]];
+    let drops: Vec<Droplet> = sets
+        .iter()
+        .map(|edges| {
+            Droplet::new(
+                DropType::Seeded(seed_for(edges, 3), edges.len()),
+                xor_blocks(&msg, edges, 4),
+            )
+        })
+        .collect();
+
+    let mut peeling = Decoder::new(msg.len(), 4);
+    for drop in drops.clone() {
+        match peeling.catch(drop) {
+            CatchResult::Missing(stats) => assert_eq!(stats.unknown_chunks, 3),
+            CatchResult::Finished(..) => panic!("peeling cannot finish"),
+        }
+    }
+
+    let mut inactivation = Decoder::with_type(msg.len(), 4, DecoderType::Inactivation);
+    for drop in drops {
+        if let CatchResult::Finished(_, stats) = inactivation.catch(drop) {
+            assert_eq!(stats.cnt_eliminated, 3);
+        }
+    }
+    assert_eq!(inactivation.data(), Some(&msg[..]));
+}
+
+fn droplets_needed(msg: &[u8], chunk_len: usize, decodertype: DecoderType) -> usize {
+    let mut enc = Encoder::ideal(msg.to_vec(), chunk_len, EncoderType::Random);
+    let mut dec = Decoder::with_type(msg.len(), chunk_len, decodertype);
+    loop {
+        if let CatchResult::Finished(data, stats) = dec.catch(enc.drop()) {
+            assert_eq!(data, msg);
+            if decodertype == DecoderType::Peeling {
+                assert_eq!(stats.cnt_eliminated, 0);
+            }
+            return stats.cnt_droplets;
+        }
+    }
+}
+
+#[test]
+fn inactivation_lowers_overhead() {
+    let mut peeling = 0;
+    let mut inactivation = 0;
+    for _ in 0..50 {
+        let len = thread_rng().gen_range(200, 800);
+        let msg: Vec<u8> = (0..len).map(|_| thread_rng().gen()).collect();
+        peeling += droplets_needed(&msg, 16, DecoderType::Peeling);
+        inactivation += droplets_needed(&msg, 16, DecoderType::Inactivation);
+    }
+    println!("peeling: {}, inactivation: {}", peeling, inactivation);
+    assert!(inactivation < peeling);
+}
+
+#[test]
+fn decode_into_borrowed_storage() {
+    let msg: Vec<u8> = (0..3_333).map(|i| (i * 7) as u8).collect();
+    let mut out = vec![0; msg.len()];
+
+    let enc = Encoder::ideal(&msg[..], 100, EncoderType::Random);
+    let mut dec = Decoder::with_storage(&mut out[..], 100, DecoderType::Inactivation);
+    for drop in enc {
+        if let CatchResult::Finished(..) = dec.catch(drop) {
+            break;
+        }
+    }
+    assert_eq!(dec.into_storage(), &msg[..]);
+}
+
+#[test]
+fn inactivation_at_large_k() {
+    // A failed elimination is retried after further droplets, until
+    // every block is known.
+    let msg: Vec<u8> = (0..64_000).map(|i| (i * 31) as u8).collect();
+    let mut enc = Encoder::ideal(msg.clone(), 16, EncoderType::Random).with_seed(5);
+    let mut dec = Decoder::with_type(msg.len(), 16, DecoderType::Inactivation);
+    loop {
+        if let CatchResult::Finished(data, stats) = dec.catch(enc.drop()) {
+            assert_eq!(data, &msg[..]);
+            assert!(stats.cnt_eliminated > 0);
+            assert!(stats.cnt_droplets < 2 * stats.cnt_chunks);
+            break;
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use fountaincode::{
+    decoder::{Decoder, DecoderType},
+    encoder::{Encoder, EncoderType},
+};
+use rand::{thread_rng, Rng};
+
+#[test]
+fn systematic_blocks_are_available_immediately() {
+    let msg: Vec<u8> = (0..1_000).map(|i| i as u8).collect();
+    let mut enc = Encoder::ideal(msg.clone(), 64, EncoderType::Systematic);
+    let mut dec = Decoder::new(msg.len(), 64);
+
+    for idx in 0..16 {
+        assert!(!dec.is_block_known(idx));
+        assert_eq!(dec.block(idx), None);
+        dec.catch(enc.drop());
+        assert_eq!(dec.recovered_blocks(), &[idx]);
+        assert!(dec.is_block_known(idx));
+        let end = std::cmp::min((idx + 1) * 64, msg.len());
+        assert_eq!(dec.block(idx), Some(&msg[idx * 64..end]));
+    }
+    // The last block holds the remaining 40 bytes only.
+    assert_eq!(dec.block(15).unwrap().len(), 40);
+    assert!(!dec.is_block_known(16));
+    assert_eq!(dec.block(16), None);
+}
+
+#[test]
+fn every_block_is_reported_once() {
+    let mut rng = thread_rng();
+    let msg: Vec<u8> = (0..5_000).map(|_| rng.gen()).collect();
+    for &decodertype in &[DecoderType::Peeling, DecoderType::Inactivation] {
+        let mut enc = Encoder::ideal(msg.clone(), 50, EncoderType::Random);
+        let mut dec = Decoder::with_type(msg.len(), 50, decodertype);
+
+        let mut reported = Vec::new();
+        while !dec.is_finished() {
+            dec.catch(enc.drop());
+            for &idx in dec.recovered_blocks() {
+                assert_eq!(dec.block(idx), Some(&msg[idx * 50..(idx + 1) * 50]));
+                reported.push(idx);
+            }
+        }
+        reported.sort_unstable();
+        assert_eq!(reported, (0..100).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,48 @@
+#![cfg(feature = "mmap")]
+
+use fountaincode::{
+    decoder::{CatchResult, Decoder, DecoderType},
+    encoder::{Encoder, EncoderType},
+    mmap,
+};
+use rand::{thread_rng, Rng};
+use std::{env, fs, path::PathBuf};
+
+fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("fountaincode-{}-{}", name, std::process::id()))
+}
+
+fn roundtrip(name: &str, len: usize, blocksize: usize, decodertype: DecoderType) {
+    let mut rng = thread_rng();
+    let msg: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+    let src = temp_path(&format!("{}-src", name));
+    let dst = temp_path(&format!("{}-dst", name));
+    fs::write(&src, &msg).unwrap();
+
+    let input = unsafe { mmap::map_input(&src) }.unwrap();
+    let output = unsafe { mmap::map_output(&dst, len as u64) }.unwrap();
+    let enc = Encoder::ideal(input, blocksize, EncoderType::Random);
+    let mut dec = Decoder::with_storage(output, blocksize, decodertype);
+    for drop in enc {
+        if let CatchResult::Finished(data, _) = dec.catch(drop) {
+            assert_eq!(data, msg);
+            break;
+        }
+    }
+    dec.into_storage().flush().unwrap();
+
+    let result = fs::read(&dst).unwrap();
+    fs::remove_file(&src).unwrap();
+    fs::remove_file(&dst).unwrap();
+    assert_eq!(result, msg);
+}
+
+#[test]
+fn mmap_roundtrip_full_blocks() {
+    roundtrip("full", 64 * 1024, 1024, DecoderType::Peeling);
+}
+
+#[test]
+fn mmap_roundtrip_partial_last_block() {
+    roundtrip("partial", 50_001, 1000, DecoderType::Inactivation);
+}
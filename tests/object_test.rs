@@ -0,0 +1,201 @@
+use fountaincode::{
+    builder::{ConfigError, DecoderBuilder, EncoderBuilder},
+    decoder::{DecodeError, DecoderType},
+    droplet::{DropType, Droplet},
+    encoder::EncoderType,
+    header::EdgeGenerator,
+    object::{ObjectDecoder, ObjectEncoder, Partition},
+    stream::{StreamEncoder, TaggedDroplet},
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::io::Cursor;
+
+#[test]
+fn partition_is_balanced() {
+    for &(len, blocksize, max, z) in &[
+        (1_000, 10, 30, 4),
+        (1_001, 10, 30, 4),
+        (1_000, 10, 100, 1),
+        (1_000, 10, 1, 100),
+        (7, 10, 3, 1),
+    ] {
+        let partition = Partition::new(len, blocksize, max).unwrap();
+        assert_eq!(partition.cnt_source_blocks(), z);
+        let mut end = 0;
+        for sbn in 0..z {
+            let block = partition.block(sbn);
+            assert_eq!(block.start, end);
+            assert!(partition.block_symbols(sbn) <= max);
+            assert!(partition.block_symbols(sbn) + 1 >= partition.block_symbols(0));
+            end = block.end;
+        }
+        assert_eq!(end, len);
+    }
+
+    let partition = Partition::new(0, 10, 10).unwrap();
+    assert!(partition.is_empty());
+    assert_eq!(partition.cnt_source_blocks(), 0);
+
+    let partition = Partition::new(1_001, 10, 30).unwrap();
+    let symbols: Vec<usize> = (0..4).map(|sbn| partition.block_symbols(sbn)).collect();
+    assert_eq!(symbols, vec![26, 25, 25, 25]);
+    assert_eq!(partition.block(1), 260..510);
+    assert_eq!(partition.block(3), 760..1_001);
+}
+
+#[test]
+fn invalid_partitions() {
+    assert_eq!(Partition::new(10, 0, 10), Err(ConfigError::ZeroBlocksize));
+    assert_eq!(
+        Partition::new(10, 10, 0),
+        Err(ConfigError::ZeroBlockSymbols)
+    );
+    assert_eq!(
+        Partition::new(1 << 40, 1, 128).err(),
+        Some(ConfigError::TooManySourceBlocks(1 << 33))
+    );
+    assert_eq!(
+        ObjectEncoder::new(Vec::new(), 10, 10, EncoderType::Random).err(),
+        Some(ConfigError::EmptyMessage)
+    );
+    assert_eq!(
+        ObjectDecoder::new(0, 10, 10).err(),
+        Some(ConfigError::EmptyMessage)
+    );
+    assert!(ObjectDecoder::new(10, 10, 0).is_err());
+}
+
+#[test]
+fn decode_object_with_loss() {
+    let mut rng = StdRng::seed_from_u64(11);
+    let msg: Vec<u8> = (0..50_000).map(|_| rng.gen()).collect();
+    for encodertype in &[EncoderType::Systematic, EncoderType::Random] {
+        let enc = ObjectEncoder::new(&msg[..], 100, 64, encodertype.clone())
+            .unwrap()
+            .with_seed(5);
+        let mut dec = ObjectDecoder::new(msg.len(), 100, 64).unwrap();
+        assert_eq!(dec.partitioning(), enc.partitioning());
+        let z = dec.partitioning().cnt_source_blocks();
+        assert_eq!(z, 8);
+
+        for drop in enc {
+            if rng.gen::<f32>() < 0.2 {
+                continue;
+            }
+            let sbn = drop.sbn as usize;
+            let finished = dec.catch(drop).unwrap();
+            assert!(dec.block_statistics(sbn).cnt_droplets > 0);
+            if finished {
+                break;
+            }
+        }
+        assert_eq!(dec.cnt_finished_blocks(), z);
+        for sbn in 0..z {
+            assert!(dec.is_block_finished(sbn));
+            let stats = dec.block_statistics(sbn);
+            assert_eq!(stats.unknown_chunks, 0);
+            assert_eq!(stats.cnt_chunks, dec.partitioning().block_symbols(sbn));
+        }
+        assert_eq!(dec.into_data(), msg);
+    }
+}
+
+#[test]
+fn droplets_are_round_robin_and_reproducible() {
+    let msg: Vec<u8> = (0..10_000).map(|i| (i * 3) as u8).collect();
+    let mut enc = ObjectEncoder::new(msg.clone(), 50, 40, EncoderType::Random)
+        .unwrap()
+        .with_seed(1);
+    let z = enc.partitioning().cnt_source_blocks();
+    assert_eq!(z, 5);
+    let drops: Vec<TaggedDroplet> = enc.by_ref().take(50).collect();
+    for (n, drop) in drops.iter().enumerate() {
+        assert_eq!(drop.sbn as usize, n % z);
+        assert_eq!(&enc.drop_at(n), drop);
+    }
+    let again = ObjectEncoder::new(msg, 50, 40, EncoderType::Random)
+        .unwrap()
+        .with_seed(1);
+    assert_eq!(again.take(50).collect::<Vec<_>>(), drops);
+}
+
+#[test]
+fn unknown_source_blocks_are_rejected() {
+    let mut dec = ObjectDecoder::new(1_000, 10, 30).unwrap();
+    let drop = TaggedDroplet::new(4, Droplet::new(DropType::Edges(0), vec![0; 10]));
+    assert_eq!(
+        dec.catch(drop),
+        Err(DecodeError::UnknownSourceBlock {
+            sbn: 4,
+            cnt_source_blocks: 4
+        })
+    );
+    for sbn in 0..4 {
+        assert_eq!(dec.block_statistics(sbn).cnt_droplets, 0);
+    }
+
+    // Malformed droplets of a known source block are rejected
+    let drop = TaggedDroplet::new(0, Droplet::new(DropType::Edges(0), vec![0; 9]));
+    assert!(dec.catch(drop).is_err());
+}
+
+#[test]
+fn object_from_builder() {
+    let msg: Vec<u8> = (0..20_000).map(|i| (i * 7) as u8).collect();
+    let builder = EncoderBuilder::new(&msg[..])
+        .blocksize(64)
+        .encoder_type(EncoderType::Systematic)
+        .c(0.1)
+        .delta(0.1)
+        .seed(3)
+        .generator(EdgeGenerator::SplitMix64);
+    let enc = ObjectEncoder::from_builder(builder.clone(), 100).unwrap();
+    assert_eq!(enc.seed(), 3);
+    let again = ObjectEncoder::new(&msg[..], 64, 100, EncoderType::Systematic)
+        .unwrap()
+        .with_seed(3);
+    assert_eq!(enc.partitioning(), again.partitioning());
+    // Same seeds, but a different distribution and generator
+    assert_ne!(
+        enc.clone().take(1_000).collect::<Vec<_>>(),
+        again.take(1_000).collect::<Vec<_>>()
+    );
+    let same = ObjectEncoder::from_builder(builder, 100).unwrap();
+    assert_eq!(
+        enc.clone().take(1_000).collect::<Vec<_>>(),
+        same.take(1_000).collect::<Vec<_>>()
+    );
+
+    let decoder = DecoderBuilder::new(msg.len())
+        .blocksize(64)
+        .decoder_type(DecoderType::Inactivation)
+        .generator(EdgeGenerator::SplitMix64);
+    let mut dec = ObjectDecoder::from_builder(decoder, 100).unwrap();
+    assert_eq!(dec.partitioning(), enc.partitioning());
+    for drop in enc.skip(1_000) {
+        if dec.catch(drop).unwrap() {
+            break;
+        }
+    }
+    assert_eq!(dec.into_data(), msg);
+}
+
+#[test]
+fn object_and_stream_share_the_partitioning() {
+    let msg: Vec<u8> = (0..10_000).map(|i| i as u8).collect();
+    let builder = EncoderBuilder::new(()).blocksize(64).seed(9);
+    let stream = StreamEncoder::from_builder(Cursor::new(&msg), 30, builder.clone()).unwrap();
+    let object = ObjectEncoder::from_builder(builder.data(&msg[..]), 30).unwrap();
+    assert_eq!(stream.partitioning(), object.partitioning());
+
+    // Every source block is encoded with the same seed
+    let mut stream = stream;
+    for sbn in 0..object.partitioning().cnt_source_blocks() {
+        let z = object.partitioning().cnt_source_blocks();
+        let enc = stream.source_block(sbn).unwrap();
+        assert_eq!(
+            enc.drop_at(2).droptype,
+            object.drop_at(2 * z + sbn).droplet.droptype
+        );
+    }
+}
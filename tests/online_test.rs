@@ -0,0 +1,57 @@
+use fountaincode::{
+    decoder::{CatchResult, DecoderType},
+    online::{Decoder, Encoder, OnlineDistribution, OnlineParams},
+};
+use rand::{thread_rng, Rng};
+
+#[test]
+fn online_distribution() {
+    let dist = OnlineDistribution::new(0.01);
+    // F = ceil(ln(0.01^2 / 4) / ln(1 - 0.01 / 2))
+    assert_eq!(dist.max_degree(), 2115);
+    let total: f64 = (0..=dist.max_degree() + 1).map(|d| dist.pmf(d)).sum();
+    assert!((total - 1.0).abs() < 1e-9);
+    assert!((dist.pmf(1) - (1.0 - (1.0 + 1.0 / 2115.0) / 1.01)).abs() < 1e-12);
+    assert_eq!(dist.pmf(0), 0.0);
+    assert_eq!(dist.pmf(2116), 0.0);
+}
+
+fn droplets_needed(msg: &[u8], blocksize: usize, params: OnlineParams, loss: f32) -> usize {
+    let mut enc = Encoder::new(msg.to_vec(), blocksize, params);
+    let mut dec = Decoder::with_type(msg.len(), blocksize, params, DecoderType::Peeling);
+    let mut loss_rng = thread_rng();
+    loop {
+        let drop = enc.drop();
+        if loss_rng.gen::<f32>() < loss {
+            continue;
+        }
+        if let CatchResult::Finished(data, stats) = dec.catch(drop) {
+            assert_eq!(data, msg);
+            return stats.cnt_droplets;
+        }
+    }
+}
+
+#[test]
+fn online_enc_dec_with_loss() {
+    let mut rng = thread_rng();
+    for &(len, blocksize) in &[(1, 1), (100, 7), (1024, 16), (5000, 10)] {
+        let msg: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+        for &params in &[OnlineParams::default(), OnlineParams { epsilon: 0.1, q: 5 }] {
+            for &loss in &[0.0, 0.3] {
+                droplets_needed(&msg, blocksize, params, loss);
+            }
+        }
+    }
+}
+
+#[test]
+fn online_overhead() {
+    let msg: Vec<u8> = (0..20_000).map(|i| i as u8).collect();
+    let params = OnlineParams { epsilon: 0.1, q: 3 };
+    let total: usize = (0..10)
+        .map(|_| droplets_needed(&msg, 10, params, 0.0))
+        .sum();
+    // (1 + 3 epsilon) n leaves room for the variance of small n.
+    assert!(total < 10 * 2600, "needed {} droplets", total);
+}
@@ -0,0 +1,93 @@
+use fountaincode::{
+    decoder::{CatchResult, Decoder, DecoderType},
+    encoder::{Encoder, EncoderType},
+    precode::Precode,
+};
+use rand::{thread_rng, Rng};
+
+fn droplets_needed(
+    msg: &[u8],
+    blocksize: usize,
+    precode: Option<&Precode>,
+    decodertype: DecoderType,
+    loss: f32,
+) -> usize {
+    let (mut enc, mut dec) = match precode {
+        Some(precode) => (
+            Encoder::ideal(
+                precode.encode(msg.to_vec(), blocksize),
+                blocksize,
+                EncoderType::Random,
+            ),
+            precode.decoder(msg.len(), blocksize, decodertype),
+        ),
+        None => (
+            Encoder::ideal(msg.to_vec(), blocksize, EncoderType::Random),
+            Decoder::with_type(msg.len(), blocksize, decodertype),
+        ),
+    };
+    let mut loss_rng = thread_rng();
+    loop {
+        let drop = enc.drop();
+        if loss_rng.gen::<f32>() < loss {
+            continue;
+        }
+        if let CatchResult::Finished(data, stats) = dec.catch(drop) {
+            assert_eq!(data, msg);
+            return stats.cnt_droplets;
+        }
+    }
+}
+
+#[test]
+fn precode_enc_dec_with_loss() {
+    let mut rng = thread_rng();
+    for &(len, blocksize, cnt_parity, degree) in &[
+        (1_usize, 1, 1, 1),
+        (100, 7, 2, 2),
+        (1024, 16, 5, 3),
+        (5000, 10, 20, 4),
+    ] {
+        let msg: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+        let precode = Precode::new(len.div_ceil(blocksize), cnt_parity, degree);
+        assert_eq!(precode.cnt_blocks(), len.div_ceil(blocksize) + cnt_parity);
+        for &decodertype in &[DecoderType::Peeling, DecoderType::Inactivation] {
+            for &loss in &[0.0, 0.3] {
+                droplets_needed(&msg, blocksize, Some(&precode), decodertype, loss);
+            }
+        }
+    }
+}
+
+#[test]
+fn precode_is_systematic() {
+    let msg: Vec<u8> = (0..100).collect();
+    let precode = Precode::new(10, 4, 2);
+    let intermediate = precode.encode(msg.clone(), 10);
+    assert_eq!(intermediate.len(), 140);
+    assert_eq!(&intermediate[..100], &msg[..]);
+    // The same parameters always yield the same parity blocks.
+    assert_eq!(Precode::new(10, 4, 2).encode(msg, 10), intermediate);
+}
+
+#[test]
+fn precode_lowers_overhead() {
+    // Elimination stalls on the blocks no droplet covers, the parity
+    // blocks fill them in.
+    let msg: Vec<u8> = (0..10_000).map(|i| i as u8).collect();
+    let precode = Precode::new(1000, 30, 3);
+    let runs = 10;
+    let needed = |precode| -> usize {
+        (0..runs)
+            .map(|_| droplets_needed(&msg, 10, precode, DecoderType::Inactivation, 0.0))
+            .sum()
+    };
+    let plain = needed(None);
+    let precoded = needed(Some(&precode));
+    assert!(
+        precoded < plain,
+        "plain: {}, precoded: {}",
+        plain / runs,
+        precoded / runs
+    );
+}
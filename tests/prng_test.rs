@@ -0,0 +1,95 @@
+use fountaincode::{
+    decoder::{CatchResult, Decoder},
+    encoder::{Encoder, EncoderType},
+    header::EdgeGenerator,
+    prng::SplitMix64,
+};
+use proptest::prelude::*;
+use rand::{RngCore, SeedableRng};
+
+// Droplets have to decode across crate versions and with other
+// implementations, so these values must never change.
+#[test]
+fn splitmix64_vectors() {
+    let mut rng = SplitMix64::new(0);
+    let outputs: Vec<u64> = (0..4).map(|_| rng.next_u64()).collect();
+    assert_eq!(
+        outputs,
+        vec![
+            0xe220_a839_7b1d_cdaf,
+            0x6e78_9e6a_a1b9_65f4,
+            0x06c4_5d18_8009_454f,
+            0xf88b_b8a8_724c_81ec
+        ]
+    );
+
+    let mut rng = SplitMix64::new(0xdead_beef);
+    let indices: Vec<usize> = (0..8).map(|_| rng.below(10)).collect();
+    assert_eq!(indices, vec![2, 8, 0, 4, 2, 6, 3, 6]);
+}
+
+#[test]
+fn splitmix64_edges() {
+    assert_eq!(
+        EdgeGenerator::SplitMix64.sample(42, 1000, 8),
+        vec![741, 159, 278, 344, 38, 868, 218, 800]
+    );
+}
+
+#[test]
+fn splitmix64_distinct_edges() {
+    assert_eq!(EdgeGenerator::default(), EdgeGenerator::SplitMix64Distinct);
+    assert_eq!(
+        EdgeGenerator::SplitMix64Distinct.sample(42, 1000, 8),
+        vec![736, 158, 277, 342, 37, 866, 218, 800]
+    );
+    assert_eq!(
+        EdgeGenerator::SplitMix64Distinct.sample(7, 10, 6),
+        vec![1, 0, 6, 4, 8, 2]
+    );
+    // A degree above k covers every block once.
+    let mut all = EdgeGenerator::SplitMix64Distinct.sample(7, 5, 9);
+    all.sort_unstable();
+    assert_eq!(all, vec![0, 1, 2, 3, 4]);
+}
+
+proptest! {
+    #[test]
+    fn splitmix64_distinct_in_range(seed: u64, k in 1_usize..500, degree in 1_usize..600) {
+        let mut sample = EdgeGenerator::SplitMix64Distinct.sample(seed, k, degree);
+        prop_assert_eq!(sample.len(), degree.min(k));
+        prop_assert!(sample.iter().all(|&idx| idx < k));
+        sample.sort_unstable();
+        sample.dedup();
+        prop_assert_eq!(sample.len(), degree.min(k));
+    }
+}
+
+#[test]
+fn legacy_generator_roundtrip() {
+    let msg: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+    let enc = Encoder::ideal(msg.clone(), 16, EncoderType::Random)
+        .with_generator(EdgeGenerator::StdRngUniform);
+    let mut dec = Decoder::from_header(&enc.header());
+    for drop in enc {
+        if let CatchResult::Finished(data, _) = dec.catch(drop) {
+            assert_eq!(data, msg);
+            return;
+        }
+    }
+}
+
+#[test]
+fn splitmix64_rng_core() {
+    let mut rng = SplitMix64::seed_from_u64(0);
+    assert_eq!(RngCore::next_u64(&mut rng), 0xe220_a839_7b1d_cdaf);
+    assert_eq!(rng.next_u32(), 0x6e78_9e6a);
+
+    let mut rng = SplitMix64::from_seed(0_u64.to_le_bytes());
+    let mut bytes = [0; 10];
+    rng.fill_bytes(&mut bytes);
+    assert_eq!(
+        bytes,
+        [0xaf, 0xcd, 0x1d, 0x7b, 0x39, 0xa8, 0x20, 0xe2, 0xf4, 0x65]
+    );
+}
@@ -0,0 +1,208 @@
+use fountaincode::raptorq::{
+    Decoder, Encoder, EncodingPacket, ObjectTransmissionInformation, PayloadId, RaptorQError,
+};
+use rand::{thread_rng, Rng};
+
+// Repair symbols of the object 0, 1, 2, ... as produced by another
+// RFC 6330 implementation.
+#[test]
+fn raptorq_reference_vectors() {
+    let msg: Vec<u8> = (0..40).collect();
+    let oti = ObjectTransmissionInformation::new(40, 4, 1, 1, 1).unwrap();
+    let enc = Encoder::new(&msg, oti).unwrap();
+    let repair: Vec<Vec<u8>> = enc.blocks()[0]
+        .repair_packets(0, 4)
+        .into_iter()
+        .map(|p| p.data)
+        .collect();
+    assert_eq!(
+        repair,
+        vec![
+            vec![19, 144, 8, 139],
+            vec![42, 209, 193, 58],
+            vec![41, 109, 161, 229],
+            vec![137, 253, 97, 21],
+        ]
+    );
+
+    // K = 3 is padded to K' = 10, repair ESIs start at K.
+    let msg: Vec<u8> = (0..12).collect();
+    let oti = ObjectTransmissionInformation::new(12, 4, 1, 1, 1).unwrap();
+    let enc = Encoder::new(&msg, oti).unwrap();
+    let repair = enc.blocks()[0].repair_packets(0, 4);
+    assert_eq!(repair[0].payload_id, PayloadId::new(0, 3));
+    let repair: Vec<Vec<u8>> = repair.into_iter().map(|p| p.data).collect();
+    assert_eq!(
+        repair,
+        vec![
+            vec![255, 159, 63, 95],
+            vec![129, 191, 253, 195],
+            vec![9, 100, 211, 190],
+            vec![156, 14, 165, 55],
+        ]
+    );
+}
+
+fn transfer(msg: &[u8], oti: ObjectTransmissionInformation, loss: f32) -> usize {
+    let enc = Encoder::new(msg, oti).unwrap();
+    let mut dec = Decoder::new(oti);
+    let mut loss_rng = thread_rng();
+    let mut cnt = 0;
+
+    for packet in enc {
+        if loss_rng.gen::<f32>() < loss {
+            continue;
+        }
+        cnt += 1;
+        let packet = EncodingPacket::from_bytes(&packet.to_bytes()).unwrap();
+        if let Some(data) = dec.catch(packet) {
+            assert_eq!(data, msg);
+            return cnt;
+        }
+    }
+    unreachable!()
+}
+
+#[test]
+fn raptorq_enc_dec_with_loss() {
+    let mut rng = thread_rng();
+    let params = [
+        (1, 1, 1, 1),
+        (100, 10, 1, 1),
+        (4321, 64, 1, 1),
+        (4321, 64, 3, 1),
+        (9999, 128, 2, 4),
+        (20_000, 96, 5, 3),
+    ];
+    for &(len, t, z, n) in &params {
+        let msg: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+        for &loss in &[0.0, 0.1, 0.5] {
+            let oti = ObjectTransmissionInformation::new(len as u64, t, z, n, 1).unwrap();
+            transfer(&msg, oti, loss);
+        }
+    }
+}
+
+#[test]
+fn raptorq_low_overhead() {
+    // RaptorQ almost always decodes from exactly K symbols.
+    let msg: Vec<u8> = (0..64 * 500).map(|i| (i % 253) as u8).collect();
+    let oti = ObjectTransmissionInformation::new(msg.len() as u64, 64, 1, 1, 1).unwrap();
+    let mut total = 0;
+    for _ in 0..10 {
+        total += transfer(&msg, oti, 0.3);
+    }
+    assert!(total <= 10 * 502, "needed {} symbols", total);
+}
+
+#[test]
+fn raptorq_decode_from_repair_only() {
+    let msg: Vec<u8> = (0..3000).map(|i| (i * 7) as u8).collect();
+    let enc = Encoder::with_defaults(&msg, 100).unwrap();
+    let mut dec = Decoder::new(enc.oti());
+
+    let block = &enc.blocks()[0];
+    let k = block.cnt_source_symbols() as u32;
+    for packet in block.repair_packets(0, k + 10) {
+        if dec.catch(packet).is_some() {
+            break;
+        }
+    }
+    assert!(dec.is_decoded());
+    assert_eq!(dec.into_data(), msg);
+}
+
+#[test]
+fn raptorq_decoded_object_is_borrowed() {
+    let msg: Vec<u8> = (0..3000).map(|i| (i * 11) as u8).collect();
+    let enc = Encoder::with_defaults(&msg, 100).unwrap();
+    let mut dec = Decoder::new(enc.oti());
+    let mut packets = enc.blocks()[0].source_packets();
+    let last = packets.pop().unwrap();
+    for packet in packets {
+        assert_eq!(dec.catch(packet), None);
+    }
+    assert!(!dec.is_decoded());
+    assert_eq!(dec.catch(last.clone()), Some(&msg[..]));
+    // Further packets return the same object
+    let ptr = dec.catch(last).unwrap().as_ptr();
+    assert_eq!(
+        dec.catch(enc.blocks()[0].repair_packets(0, 1).remove(0))
+            .unwrap()
+            .as_ptr(),
+        ptr
+    );
+}
+
+// Once K symbols arrived, a failed solve is repeated after 1, 2, 4, ...
+// more symbols rather than for every one, yet decoding still needs only
+// a few symbols more than K.
+#[test]
+fn raptorq_solve_schedule() {
+    let msg: Vec<u8> = (0..64 * 1_000).map(|i| (i % 251) as u8).collect();
+    let oti = ObjectTransmissionInformation::new(msg.len() as u64, 64, 1, 1, 1).unwrap();
+    let enc = Encoder::new(&msg, oti).unwrap();
+    let block = &enc.blocks()[0];
+    let k = block.cnt_source_symbols() as u32;
+    let mut total = 0;
+    for round in 0..10 {
+        let mut dec = Decoder::new(oti);
+        // Half of the source symbols, then repair symbols only
+        let packets = block
+            .source_packets()
+            .into_iter()
+            .step_by(2)
+            .chain(block.repair_packets(round * 2 * k, 2 * k));
+        for (cnt, packet) in packets.enumerate() {
+            if dec.catch(packet).is_some() {
+                total += cnt + 1;
+                break;
+            }
+        }
+        assert!(dec.is_decoded());
+    }
+    assert!(total <= 10 * (k as usize + 4), "needed {} symbols", total);
+}
+
+#[test]
+fn raptorq_oti() {
+    let oti = ObjectTransmissionInformation::new(1 << 33, 1024, 200, 4, 8).unwrap();
+    let bytes = oti.to_bytes();
+    assert_eq!(bytes, [2, 0, 0, 0, 0, 0, 4, 0, 200, 0, 4, 8]);
+    assert_eq!(ObjectTransmissionInformation::from_bytes(&bytes), Ok(oti));
+
+    let oti = ObjectTransmissionInformation::with_defaults(100 << 20, 1024).unwrap();
+    assert_eq!(oti.source_blocks(), 25);
+    assert_eq!(oti.sub_blocks(), 1);
+
+    assert_eq!(
+        ObjectTransmissionInformation::new(0, 1024, 1, 1, 1),
+        Err(RaptorQError::InvalidTransferLength(0))
+    );
+    assert_eq!(
+        ObjectTransmissionInformation::new(100, 10, 1, 1, 4),
+        Err(RaptorQError::InvalidSymbolSize {
+            symbol_size: 10,
+            alignment: 4
+        })
+    );
+    assert_eq!(
+        ObjectTransmissionInformation::new(100, 8, 1, 3, 4),
+        Err(RaptorQError::InvalidSubBlocks(3))
+    );
+    assert_eq!(
+        ObjectTransmissionInformation::new(100, 10, 11, 1, 1),
+        Err(RaptorQError::InvalidSourceBlocks(11))
+    );
+    assert_eq!(
+        ObjectTransmissionInformation::new(56_404, 1, 1, 1, 1),
+        Err(RaptorQError::TooManySourceSymbols(56_404))
+    );
+    assert_eq!(
+        ObjectTransmissionInformation::from_bytes(&bytes[..11]),
+        Err(RaptorQError::Truncated {
+            needed: 12,
+            available: 11
+        })
+    );
+}
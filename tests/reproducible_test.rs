@@ -0,0 +1,132 @@
+use fountaincode::{
+    decoder::{CatchResult, Decoder},
+    droplet::{DropType, Droplet},
+    encoder::{Encoder, EncoderType},
+    prng::SplitMix64,
+};
+use rand::{rngs::StdRng, SeedableRng};
+use std::collections::HashSet;
+
+fn msg() -> Vec<u8> {
+    (0..3_000).map(|i| (i * 31) as u8).collect()
+}
+
+#[test]
+fn same_seed_same_droplets() {
+    for encodertype in &[EncoderType::Systematic, EncoderType::Random] {
+        let a: Vec<Droplet> = Encoder::ideal(msg(), 100, encodertype.clone())
+            .with_seed(7)
+            .take(100)
+            .collect();
+        let b: Vec<Droplet> = Encoder::ideal(msg(), 100, encodertype.clone())
+            .with_seed(7)
+            .take(100)
+            .collect();
+        assert_eq!(a, b);
+
+        let c: Vec<Droplet> = Encoder::ideal(msg(), 100, encodertype.clone())
+            .with_seed(8)
+            .take(100)
+            .collect();
+        assert_ne!(a, c);
+    }
+}
+
+#[test]
+fn drop_at_regenerates_droplets() {
+    for encodertype in &[EncoderType::Systematic, EncoderType::Random] {
+        let mut enc =
+            Encoder::robust(msg(), 100, encodertype.clone(), 0.2, None, 0.05).with_seed(99);
+        let drops: Vec<Droplet> = enc.by_ref().take(100).collect();
+        assert_eq!(enc.cnt, 100);
+        for (n, drop) in drops.iter().enumerate().rev() {
+            assert_eq!(&enc.drop_at(n), drop);
+        }
+    }
+}
+
+// The droplets of a master seed must not change across releases, so
+// that they can be regenerated later, like `splitmix64_vectors`.
+#[test]
+fn drop_at_vectors() {
+    let enc = Encoder::robust(msg(), 100, EncoderType::Random, 0.2, None, 0.05).with_seed(1);
+    let droptypes: Vec<DropType> = [0, 1, 2, 1_000]
+        .iter()
+        .map(|&n| enc.drop_at(n).droptype)
+        .collect();
+    assert_eq!(
+        droptypes,
+        vec![
+            DropType::Seeded(0xa738_22ac_2ea5_4ab1, 2),
+            DropType::Seeded(0x9c00_02e0_1d4a_175e, 2),
+            DropType::Seeded(0xbfcb_6b6d_8a2f_dfb5, 4),
+            DropType::Seeded(0xa704_13e4_acf8_4b0d, 2),
+        ]
+    );
+}
+
+// Droplet n + 1 must not continue the stream of droplet n, neither
+// for the same master seed nor for one a SplitMix64 step apart.
+#[test]
+fn droplets_do_not_share_a_stream() {
+    let enc = Encoder::ideal(msg(), 100, EncoderType::Random).with_seed(0);
+    let shifted = Encoder::ideal(msg(), 100, EncoderType::Random).with_seed(0x9e37_79b9_7f4a_7c15);
+    let mut seeds = HashSet::new();
+    for n in 0..1_000 {
+        for drop in &[enc.drop_at(n), shifted.drop_at(n)] {
+            match drop.droptype {
+                DropType::Seeded(seed, _) => assert!(seeds.insert(seed)),
+                DropType::Edges(_) => panic!("random encoders only send seeded droplets"),
+            }
+        }
+    }
+}
+
+#[test]
+fn splitmix64_is_the_default_rng() {
+    let enc = Encoder::ideal(msg(), 100, EncoderType::Random).with_seed(5);
+    let explicit = Encoder::ideal(msg(), 100, EncoderType::Random)
+        .with_rng(SplitMix64::new(0))
+        .with_seed(5);
+    for n in 0..20 {
+        assert_eq!(enc.drop_at(n), explicit.drop_at(n));
+    }
+}
+
+#[test]
+fn seed_is_exposed() {
+    let enc = Encoder::ideal(msg(), 100, EncoderType::Random);
+    let again = Encoder::ideal(msg(), 100, EncoderType::Random).with_seed(enc.seed());
+    assert_eq!(enc.drop_at(12), again.drop_at(12));
+}
+
+#[test]
+fn caller_supplied_rng() {
+    let a = Encoder::ideal(msg(), 100, EncoderType::Random).with_rng(StdRng::seed_from_u64(3));
+    let b = Encoder::ideal(msg(), 100, EncoderType::Random).with_rng(StdRng::seed_from_u64(3));
+    assert_eq!(a.seed(), b.seed());
+    assert_eq!(
+        a.take(50).collect::<Vec<_>>(),
+        b.take(50).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn decode_regenerated_droplets() {
+    let enc = Encoder::ideal(msg(), 100, EncoderType::Random).with_seed(1);
+    let mut dec = Decoder::new(msg().len(), 100);
+    // Droplets can be produced in any order, e.g. to retransmit
+    for n in (0..10_000).rev() {
+        if let CatchResult::Finished(data, _) = dec.catch(enc.drop_at(n)) {
+            assert_eq!(data, &msg()[..]);
+            return;
+        }
+    }
+    panic!("decoding did not finish");
+}
+
+#[test]
+#[should_panic(expected = "empty message")]
+fn empty_message_panics() {
+    Encoder::ideal(Vec::new(), 100, EncoderType::Random).drop_at(0);
+}
@@ -3,14 +3,14 @@ use fountaincode::{
     encoder::{Encoder, EncoderType},
 };
 use proptest::prelude::*;
-use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use rand::{distributions::Alphanumeric, rngs::StdRng, Rng, SeedableRng};
 use stopwatch::Stopwatch;
 
 proptest! {
     #![proptest_config(ProptestConfig::with_cases(10000))]
     #[test]
-    fn robust_prop_test(total_len in 1024_usize..8192, chunk_len in 8_usize..512) {
-        let s: String = thread_rng()
+    fn robust_prop_test(total_len in 1024_usize..8192, chunk_len in 8_usize..512, seed in any::<u64>()) {
+        let s: String = StdRng::seed_from_u64(seed)
             .sample_iter(Alphanumeric)
             .take(total_len)
             .collect();
@@ -18,7 +18,7 @@ proptest! {
         let len = buf.len();
         let to_compare = buf.clone();
 
-        let mut enc = Encoder::robust(buf, chunk_len, EncoderType::Systematic, 0.2, None, 0.05);
+        let mut enc = Encoder::robust(buf, chunk_len, EncoderType::Systematic, 0.2, None, 0.05).with_seed(seed);
         let mut dec = Decoder::new(len, chunk_len);
 
         let sw = Stopwatch::start_new();
@@ -32,8 +32,8 @@ proptest! {
 proptest! {
     #![proptest_config(ProptestConfig::with_cases(10000))]
     #[test]
-    fn robust_prop_lossy_test(total_len in 1024_usize..8192, chunk_len in 8_usize..512) {
-        let s: String = thread_rng()
+    fn robust_prop_lossy_test(total_len in 1024_usize..8192, chunk_len in 8_usize..512, seed in any::<u64>()) {
+        let s: String = StdRng::seed_from_u64(seed)
             .sample_iter(Alphanumeric)
             .take(total_len)
             .collect();
@@ -45,9 +45,9 @@ proptest! {
         let mut res: Vec<u8> = vec![];
 
         for loss in &[0.1, 0.3, 0.5, 0.9] {
-            let mut enc = Encoder::robust(buf.clone(), chunk_len , EncoderType::Systematic, 0.2, None, 0.05);
+            let mut enc = Encoder::robust(buf.clone(), chunk_len , EncoderType::Systematic, 0.2, None, 0.05).with_seed(seed);
             let sw = Stopwatch::start_new();
-            res = run_lossy(&mut enc, &mut dec, *loss);
+            res = run_lossy(&mut enc, &mut dec, *loss, seed);
             println!("total_len: {:?}, chunk_len: {:?}, loss: {:?}, time: {:#?}", total_len, chunk_len, loss, sw.elapsed());
         }
 
@@ -64,14 +64,14 @@ fn run(enc: &mut Encoder, dec: &mut Decoder) -> Vec<u8> {
             }
             CatchResult::Finished(data, _stats) => {
                 // println!("Finished, stats: {:?}", stats);
-                break data;
+                break data.to_vec();
             }
         }
     }
 }
 
-fn run_lossy(enc: &mut Encoder, dec: &mut Decoder, loss: f32) -> Vec<u8> {
-    let mut loss_rng = thread_rng();
+fn run_lossy(enc: &mut Encoder, dec: &mut Decoder, loss: f32, seed: u64) -> Vec<u8> {
+    let mut loss_rng = StdRng::seed_from_u64(seed);
     loop {
         if loss_rng.gen::<f32>() > loss {
             let drop = enc.drop();
@@ -81,7 +81,7 @@ fn run_lossy(enc: &mut Encoder, dec: &mut Decoder, loss: f32) -> Vec<u8> {
                 }
                 CatchResult::Finished(data, stats) => {
                     println!("overhead: {:?}", stats.overhead);
-                    break data;
+                    break data.to_vec();
                 }
             }
         }
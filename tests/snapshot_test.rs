@@ -0,0 +1,244 @@
+use fountaincode::{
+    decoder::{
+        CatchResult, Decoder, DecoderType, SnapshotError, DEFAULT_RESTORE_LIMIT, SNAPSHOT_VERSION,
+    },
+    encoder::{Encoder, EncoderType},
+    precode::Precode,
+};
+
+// Feeds `cnt` droplets to `dec` and returns a snapshot of it.
+fn snapshot_after(enc: &mut Encoder, dec: &mut Decoder, cnt: usize) -> Vec<u8> {
+    for _ in 0..cnt {
+        dec.catch(enc.drop());
+    }
+    assert!(!dec.is_finished());
+    let mut buf = Vec::new();
+    dec.save(&mut buf).unwrap();
+    buf
+}
+
+fn finish<S: AsRef<[u8]> + AsMut<[u8]>>(enc: &mut Encoder, dec: &mut Decoder<S>) -> Vec<u8> {
+    loop {
+        if let CatchResult::Finished(data, _) = dec.catch(enc.drop()) {
+            break data.to_vec();
+        }
+    }
+}
+
+#[test]
+fn resume_after_restore() {
+    for &decodertype in &[DecoderType::Peeling, DecoderType::Inactivation] {
+        let msg: Vec<u8> = (0..5_003).map(|i| (i * 13) as u8).collect();
+        let mut enc = Encoder::robust(msg.clone(), 64, EncoderType::Random, 0.2, None, 0.05);
+        let mut dec = Decoder::with_type(msg.len(), 64, decodertype);
+        let snapshot = snapshot_after(&mut enc, &mut dec, 40);
+
+        let mut restored = Decoder::restore(&snapshot[..]).unwrap();
+        let mut buf = Vec::new();
+        restored.save(&mut buf).unwrap();
+        assert!(!Decoder::restore(&buf[..]).unwrap().is_finished());
+
+        // Both decoders take the same droplets from here on
+        let mut replay = enc.clone();
+        assert_eq!(finish(&mut enc, &mut dec), msg);
+        assert_eq!(finish(&mut replay, &mut restored), msg);
+    }
+}
+
+#[test]
+fn restore_keeps_elimination_schedule() {
+    let msg: Vec<u8> = (0..4_000).map(|i| (i * 3) as u8).collect();
+    let mut enc = Encoder::ideal(msg.clone(), 20, EncoderType::Random).with_seed(7);
+    let mut dec = Decoder::with_type(msg.len(), 20, DecoderType::Inactivation);
+    // Past the first elimination at k = 200 useful droplets
+    let snapshot = snapshot_after(&mut enc, &mut dec, 205);
+    let mut restored = Decoder::restore(&snapshot[..]).unwrap();
+    loop {
+        let drop = enc.drop();
+        let expected = match dec.catch(drop.clone()) {
+            CatchResult::Missing(stats) | CatchResult::Finished(_, stats) => stats,
+        };
+        let stats = match restored.catch(drop) {
+            CatchResult::Missing(stats) | CatchResult::Finished(_, stats) => stats,
+        };
+        assert_eq!(stats.cnt_eliminated, expected.cnt_eliminated);
+        assert_eq!(stats.unknown_chunks, expected.unknown_chunks);
+        if dec.is_finished() {
+            break;
+        }
+    }
+}
+
+#[test]
+fn restore_keeps_counters() {
+    let msg: Vec<u8> = (0..2_000).map(|i| i as u8).collect();
+    let mut enc = Encoder::ideal(msg.clone(), 100, EncoderType::Random);
+    let mut dec = Decoder::new(msg.len(), 100);
+    let drop = enc.drop();
+    dec.catch(drop.clone());
+    dec.catch(drop.clone());
+    let mut buf = Vec::new();
+    dec.save(&mut buf).unwrap();
+
+    let mut restored = Decoder::restore(&buf[..]).unwrap();
+    match restored.catch(drop) {
+        CatchResult::Missing(stats) => {
+            assert_eq!(stats.cnt_droplets, 3);
+            assert_eq!(stats.cnt_duplicates, 2);
+        }
+        CatchResult::Finished(..) => panic!("one droplet cannot finish"),
+    }
+}
+
+#[test]
+fn resume_with_precode() {
+    let blocksize = 32;
+    let msg: Vec<u8> = (0..3_200).map(|i| (i * 7) as u8).collect();
+    let precode = Precode::new(100, 10, 3);
+    let mut enc = Encoder::ideal(
+        precode.encode(msg.clone(), blocksize),
+        blocksize,
+        EncoderType::Random,
+    );
+    let mut dec = precode.decoder(msg.len(), blocksize, DecoderType::Inactivation);
+    let snapshot = snapshot_after(&mut enc, &mut dec, 50);
+
+    let mut restored = Decoder::restore(&snapshot[..]).unwrap();
+    assert_eq!(finish(&mut enc, &mut restored), msg);
+}
+
+#[test]
+fn restore_into_storage() {
+    let msg: Vec<u8> = (0..1_000).map(|i| i as u8).collect();
+    let mut enc = Encoder::ideal(msg.clone(), 100, EncoderType::Systematic);
+    let mut dec = Decoder::new(msg.len(), 100);
+    let snapshot = snapshot_after(&mut enc, &mut dec, 5);
+
+    let mut out = vec![0; msg.len()];
+    let mut restored = Decoder::restore_into(&snapshot[..], &mut out[..]).unwrap();
+    for idx in 0..5 {
+        assert!(restored.is_block_known(idx));
+    }
+    finish(&mut enc, &mut restored);
+    assert_eq!(restored.into_storage(), &msg[..]);
+
+    let mut short = vec![0; 999];
+    match Decoder::restore_into(&snapshot[..], &mut short[..]) {
+        Err(SnapshotError::LengthMismatch { expected, actual }) => {
+            assert_eq!((expected, actual), (1_000, 999));
+        }
+        _ => panic!("storage of the wrong length accepted"),
+    }
+}
+
+#[test]
+fn damaged_snapshots_are_rejected() {
+    let msg: Vec<u8> = (0..1_000).map(|i| i as u8).collect();
+    let mut enc = Encoder::ideal(msg.clone(), 100, EncoderType::Random);
+    let mut dec = Decoder::new(msg.len(), 100);
+    let snapshot = snapshot_after(&mut enc, &mut dec, 5);
+
+    let mut flipped = snapshot.clone();
+    flipped[20] ^= 1;
+    assert!(matches!(
+        Decoder::restore(&flipped[..]),
+        Err(SnapshotError::ChecksumMismatch)
+    ));
+
+    let mut magic = snapshot.clone();
+    magic[0] = b'X';
+    assert!(matches!(
+        Decoder::restore(&magic[..]),
+        Err(SnapshotError::BadMagic)
+    ));
+
+    let mut version = snapshot.clone();
+    version[4] = SNAPSHOT_VERSION + 1;
+    assert!(matches!(
+        Decoder::restore(&version[..]),
+        Err(SnapshotError::UnsupportedVersion(v)) if v == SNAPSHOT_VERSION + 1
+    ));
+
+    for len in &[0, 10, snapshot.len() - 1] {
+        assert!(Decoder::restore(&snapshot[..*len]).is_err());
+    }
+}
+
+// Bitwise CRC-32, to forge snapshots that pass the checksum.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0_u32;
+    for &b in data {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn with_checksum(mut snapshot: Vec<u8>) -> Vec<u8> {
+    let end = snapshot.len() - 4;
+    let crc = crc32(&snapshot[..end]);
+    snapshot[end..].copy_from_slice(&crc.to_be_bytes());
+    snapshot
+}
+
+#[test]
+fn oversized_snapshots_are_rejected() {
+    let msg: Vec<u8> = (0..1_000).map(|i| i as u8).collect();
+    let mut enc = Encoder::ideal(msg.clone(), 100, EncoderType::Random);
+    let mut dec = Decoder::new(msg.len(), 100);
+    let snapshot = snapshot_after(&mut enc, &mut dec, 5);
+    assert!(Decoder::restore(&with_checksum(snapshot.clone())[..]).is_ok());
+
+    // A message of 1 TB, or 4 billion auxiliary blocks, would need a
+    // far larger bitmap than the snapshot holds.
+    let mut len = snapshot.clone();
+    len[5..13].copy_from_slice(&(1_u64 << 40).to_be_bytes());
+    let mut aux = snapshot;
+    aux[17..21].copy_from_slice(&u32::MAX.to_be_bytes());
+    for forged in [len, aux] {
+        assert!(matches!(
+            Decoder::restore(&with_checksum(forged)[..]),
+            Err(SnapshotError::Corrupt)
+        ));
+    }
+}
+
+#[test]
+fn huge_blocks_are_rejected() {
+    // 256 empty blocks of 4 GiB each, which fit into a 32-byte bitmap
+    let blocksize = u32::MAX as u64;
+    let mut forged = b"FCDS".to_vec();
+    forged.push(SNAPSHOT_VERSION);
+    forged.extend_from_slice(&(256 * blocksize).to_be_bytes());
+    forged.extend_from_slice(&(blocksize as u32).to_be_bytes());
+    forged.extend_from_slice(&0_u32.to_be_bytes());
+    forged.extend_from_slice(&[0, 0]);
+    forged.extend_from_slice(&[0; 5 * 8 + 32 + 4 + 8 + 4]);
+    assert_eq!(forged.len(), 111);
+    let forged = with_checksum(forged);
+    match Decoder::restore(&forged[..]) {
+        Err(SnapshotError::TooLarge { needed, limit }) => {
+            assert_eq!((needed, limit), (256 * blocksize, DEFAULT_RESTORE_LIMIT));
+        }
+        _ => panic!("snapshot of 1 TB accepted"),
+    }
+
+    let msg: Vec<u8> = (0..1_000).map(|i| i as u8).collect();
+    let mut enc = Encoder::ideal(msg.clone(), 100, EncoderType::Random);
+    let mut dec = Decoder::new(msg.len(), 100);
+    let snapshot = snapshot_after(&mut enc, &mut dec, 5);
+    assert!(matches!(
+        Decoder::restore_with_limit(&snapshot[..], 999),
+        Err(SnapshotError::TooLarge {
+            needed: 1_000,
+            limit: 999
+        })
+    ));
+    assert!(Decoder::restore_with_limit(&snapshot[..], 1_000).is_ok());
+}
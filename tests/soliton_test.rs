@@ -1,4 +1,4 @@
-use fountaincode::soliton::Soliton;
+use fountaincode::soliton::{Soliton, SolitonError};
 use rand::{distributions::Distribution, rngs::StdRng, SeedableRng};
 
 #[test]
@@ -41,3 +41,129 @@ fn soliton_compare_test() {
     println!("r_sum2: {:?}", r_sum2);
     assert!(r_sum1 > i_sum)
 }
+
+#[test]
+fn robust_soliton_sample_range() {
+    let mut rng = StdRng::seed_from_u64(0);
+    for &k in &[1, 2, 10, 1000] {
+        let sol = Soliton::robust(k, 0.2, None, 0.05);
+        for _ in 0..10_000 {
+            let degree = sol.sample(&mut rng);
+            assert!(degree >= 1 && degree <= k, "degree {} for k {}", degree, k);
+        }
+    }
+}
+
+#[test]
+fn soliton_pmf_sums_to_one() {
+    for &k in &[1, 2, 3, 10, 100, 1000, 10_000] {
+        let sols = vec![
+            Soliton::ideal(k),
+            Soliton::robust(k, 0.2, None, 0.05),
+            Soliton::robust(k, 0.01, None, 0.5),
+            Soliton::robust(k, 1.0, Some(k), 0.05),
+        ];
+        for sol in sols {
+            let total: f64 = (0..=k + 1).map(|d| sol.pmf(d) as f64).sum();
+            assert!((total - 1.0).abs() < 1e-4, "{:?} sums to {}", sol, total);
+            assert_eq!(sol.pmf(0), 0.0);
+            assert_eq!(sol.pmf(k + 1), 0.0);
+        }
+    }
+}
+
+#[test]
+fn robust_soliton_as_in_luby() {
+    // With the spike at m = 40, R = k / m = 2.5.
+    let (k, m, delta) = (100, 40, 0.05_f32);
+    let r = k as f32 / m as f32;
+    let rho = |i: usize| {
+        if i == 1 {
+            1.0 / k as f32
+        } else {
+            1.0 / (i * (i - 1)) as f32
+        }
+    };
+    let tau = |i: usize| {
+        if i < m {
+            r / (i * k) as f32
+        } else if i == m {
+            r * (r / delta).ln() / k as f32
+        } else {
+            0.0
+        }
+    };
+    let beta: f32 = (1..=k).map(|i| rho(i) + tau(i)).sum();
+
+    let sol = Soliton::try_robust(k, 0.2, Some(m), delta).unwrap();
+    for d in 1..=k {
+        let expected = (rho(d) + tau(d)) / beta;
+        assert!((sol.pmf(d) - expected).abs() < 1e-6, "degree {}", d);
+    }
+}
+
+#[test]
+fn robust_soliton_uses_the_real_r() {
+    // R = 0.2 ln(1000 / 0.05) sqrt(1000) = 62.6, so k / R = 15.97 is
+    // floored to the spike m = 15, but tau still uses R itself.
+    let (k, c, delta) = (1000, 0.2_f32, 0.05_f32);
+    let r = c * (k as f32 / delta).ln() * (k as f32).sqrt();
+    let m = (k as f32 / r).floor() as usize;
+    assert_eq!(m, 15);
+    let rho = |i: usize| {
+        if i == 1 {
+            1.0 / k as f32
+        } else {
+            1.0 / (i * (i - 1)) as f32
+        }
+    };
+    let tau = |i: usize| {
+        if i < m {
+            r / (i as f32 * k as f32)
+        } else if i == m {
+            r * (r / delta).ln() / k as f32
+        } else {
+            0.0
+        }
+    };
+    let beta: f64 = (1..=k).map(|i| (rho(i) + tau(i)) as f64).sum();
+
+    let sol = Soliton::robust(k, c, None, delta);
+    for d in 1..=k {
+        let expected = (rho(d) + tau(d)) / beta as f32;
+        assert!((sol.pmf(d) - expected).abs() < 1e-6, "degree {}", d);
+    }
+}
+
+#[test]
+fn robust_soliton_validation() {
+    assert_eq!(
+        Soliton::try_robust(0, 0.2, None, 0.05).unwrap_err(),
+        SolitonError::NoBlocks
+    );
+    assert_eq!(
+        Soliton::try_robust(10, 0.0, None, 0.05).unwrap_err(),
+        SolitonError::InvalidC(0.0)
+    );
+    assert!(matches!(
+        Soliton::try_robust(10, f32::NAN, None, 0.05),
+        Err(SolitonError::InvalidC(_))
+    ));
+    for &delta in &[0.0, 1.0, -0.5] {
+        assert_eq!(
+            Soliton::try_robust(10, 0.2, None, delta).unwrap_err(),
+            SolitonError::InvalidDelta(delta)
+        );
+    }
+    for &spike in &[0, 11] {
+        assert_eq!(
+            Soliton::try_robust(10, 0.2, Some(spike), 0.05).unwrap_err(),
+            SolitonError::InvalidSpike { spike, k: 10 }
+        );
+    }
+    // A huge R would put the spike below 1.
+    match Soliton::try_robust(10, 100.0, None, 0.05).unwrap() {
+        Soliton::Robust { m, .. } => assert_eq!(m, 1),
+        Soliton::Ideal { .. } => unreachable!(),
+    }
+}
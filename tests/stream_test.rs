@@ -0,0 +1,211 @@
+use fountaincode::{
+    builder::{ConfigError, EncoderBuilder},
+    droplet::{DropType, Droplet},
+    encoder::EncoderType,
+    header::EdgeGenerator,
+    soliton::SolitonError,
+    stream::{StreamDecoder, StreamEncoder, TaggedDroplet},
+};
+use rand::{thread_rng, Rng};
+use std::{
+    fs,
+    io::{self, Cursor, Seek, SeekFrom},
+};
+
+fn message(len: usize) -> Vec<u8> {
+    let mut rng = thread_rng();
+    (0..len).map(|_| rng.gen()).collect()
+}
+
+#[test]
+fn stream_roundtrip_with_loss() {
+    let msg = message(100_000);
+    let mut enc = StreamEncoder::new(Cursor::new(&msg), 128, 100, EncoderType::Random)
+        .unwrap()
+        .with_droplets_per_block(50);
+    assert_eq!(enc.cnt_source_blocks(), 8);
+
+    let mut dec = StreamDecoder::new(Cursor::new(Vec::new()), enc.len(), 128, 100);
+    let mut rng = thread_rng();
+    for drop in &mut enc {
+        if rng.gen_bool(0.3) {
+            continue;
+        }
+        if dec.catch(drop.unwrap()).unwrap() {
+            break;
+        }
+    }
+    assert_eq!(dec.cnt_finished_blocks(), 8);
+    assert_eq!(dec.into_inner().into_inner(), msg);
+}
+
+#[test]
+fn stream_starts_at_reader_position() {
+    let msg = message(5_000);
+    let mut reader = Cursor::new(&msg);
+    reader.seek(SeekFrom::Start(1_000)).unwrap();
+
+    let enc = StreamEncoder::new(reader, 64, 20, EncoderType::Systematic).unwrap();
+    assert_eq!(enc.len(), 4_000);
+
+    let mut dec = StreamDecoder::new(Cursor::new(Vec::new()), enc.len(), 64, 20);
+    for drop in enc {
+        if dec.catch(drop.unwrap()).unwrap() {
+            break;
+        }
+    }
+    assert_eq!(dec.into_inner().into_inner(), &msg[1_000..]);
+}
+
+#[test]
+fn stream_file_roundtrip() {
+    let dir = std::env::temp_dir();
+    let src = dir.join(format!("fountaincode-stream-src-{}", std::process::id()));
+    let dst = dir.join(format!("fountaincode-stream-dst-{}", std::process::id()));
+    let msg = message(70_001);
+    fs::write(&src, &msg).unwrap();
+
+    let enc = StreamEncoder::open(&src, 256, 64, EncoderType::Systematic).unwrap();
+    let mut dec = StreamDecoder::new(fs::File::create(&dst).unwrap(), enc.len(), 256, 64);
+    for drop in enc {
+        if dec.catch(drop.unwrap()).unwrap() {
+            break;
+        }
+    }
+    drop(dec);
+    let result = fs::read(&dst).unwrap();
+    fs::remove_file(&src).unwrap();
+    fs::remove_file(&dst).unwrap();
+    assert_eq!(result, msg);
+}
+
+#[test]
+fn stream_ignores_foreign_blocks() {
+    let mut dec = StreamDecoder::new(Cursor::new(Vec::new()), 1_000, 100, 5);
+    assert_eq!(dec.cnt_source_blocks(), 2);
+
+    let drop = TaggedDroplet::new(7, Droplet::new(DropType::Edges(0), vec![0; 100]));
+    assert!(!dec.catch(drop).unwrap());
+    assert_eq!(dec.cnt_finished_blocks(), 0);
+}
+
+#[test]
+fn stream_empty_input() {
+    let mut enc = StreamEncoder::new(Cursor::new(Vec::new()), 64, 10, EncoderType::Random).unwrap();
+    assert!(enc.is_empty());
+    assert!(enc.next().is_none());
+
+    let dec = StreamDecoder::new(Cursor::new(Vec::new()), 0, 64, 10);
+    assert!(dec.is_finished());
+}
+
+#[test]
+fn tagged_droplet_wire_roundtrip() {
+    let drop = TaggedDroplet::new(
+        0x0102_0304,
+        Droplet::new(DropType::Seeded(99, 3), vec![4; 16]),
+    );
+    let bytes = drop.to_bytes();
+    assert_eq!(bytes.len(), drop.encoded_len());
+    assert_eq!(&bytes[..4], &[1, 2, 3, 4]);
+    assert_eq!(TaggedDroplet::from_bytes(&bytes).unwrap(), drop);
+
+    let mut buf = vec![0; drop.encoded_len()];
+    assert_eq!(drop.write_into(&mut buf).unwrap(), bytes.len());
+    assert_eq!(buf, bytes);
+    assert!(TaggedDroplet::from_bytes(&bytes[..3]).is_err());
+}
+
+#[test]
+fn systematic_stream_with_loss() {
+    // Every pass over the source blocks continues their droplet
+    // sequences, so the repair droplets after the systematic rounds
+    // make up for the lost ones.
+    let msg = message(50_000);
+    let enc = StreamEncoder::new(Cursor::new(&msg), 100, 50, EncoderType::Systematic).unwrap();
+    let mut dec = StreamDecoder::new(Cursor::new(Vec::new()), enc.len(), 100, 50);
+    let mut rng = thread_rng();
+    let mut cnt_repair = 0;
+    for drop in enc.take(100_000) {
+        let drop = drop.unwrap();
+        if let DropType::Seeded(..) = drop.droplet.droptype {
+            cnt_repair += 1;
+        }
+        if rng.gen_bool(0.5) {
+            continue;
+        }
+        if dec.catch(drop).unwrap() {
+            break;
+        }
+    }
+    assert!(cnt_repair > 0);
+    assert!(dec.is_finished());
+    assert_eq!(dec.into_inner().into_inner(), msg);
+}
+
+#[test]
+fn stream_from_builder() {
+    let msg = message(30_000);
+    let builder = EncoderBuilder::new(())
+        .blocksize(64)
+        .c(0.1)
+        .delta(0.1)
+        .seed(11)
+        .generator(EdgeGenerator::SplitMix64);
+    let enc = StreamEncoder::from_builder(Cursor::new(&msg), 100, builder.clone()).unwrap();
+    assert_eq!(enc.seed(), 11);
+    let again = StreamEncoder::from_builder(Cursor::new(&msg), 100, builder).unwrap();
+    let drops: Vec<TaggedDroplet> = enc.take(1_000).map(Result::unwrap).collect();
+    let same: Vec<TaggedDroplet> = again.take(1_000).map(Result::unwrap).collect();
+    assert_eq!(drops, same);
+
+    let mut dec = StreamDecoder::new(Cursor::new(Vec::new()), msg.len() as u64, 64, 100)
+        .with_generator(EdgeGenerator::SplitMix64);
+    let enc = StreamEncoder::from_builder(
+        Cursor::new(&msg),
+        100,
+        EncoderBuilder::new(())
+            .blocksize(64)
+            .generator(EdgeGenerator::SplitMix64),
+    )
+    .unwrap();
+    for drop in enc {
+        if dec.catch(drop.unwrap()).unwrap() {
+            break;
+        }
+    }
+    assert_eq!(dec.into_inner().into_inner(), msg);
+}
+
+#[test]
+fn stream_rejects_invalid_settings() {
+    let msg = message(1_000);
+    let err = |builder: EncoderBuilder<()>, block_symbols| {
+        let err = StreamEncoder::from_builder(Cursor::new(&msg), block_symbols, builder)
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        err.into_inner()
+            .unwrap()
+            .downcast::<ConfigError>()
+            .map(|err| *err)
+            .unwrap()
+    };
+    assert_eq!(
+        err(EncoderBuilder::new(()).blocksize(0), 10),
+        ConfigError::ZeroBlocksize
+    );
+    assert_eq!(
+        err(EncoderBuilder::new(()).blocksize(64), 0),
+        ConfigError::ZeroBlockSymbols
+    );
+    // 16 symbols in source blocks of 6, 5 and 5 symbols
+    assert_eq!(
+        err(EncoderBuilder::new(()).blocksize(64).spike(6), 7),
+        ConfigError::Soliton(SolitonError::InvalidSpike { spike: 6, k: 5 })
+    );
+    assert_eq!(
+        err(EncoderBuilder::new(()).blocksize(1 << 32), 7),
+        ConfigError::BlocksizeTooLarge(1 << 32)
+    );
+}
@@ -0,0 +1,118 @@
+use fountaincode::{
+    builder::{ConfigError, EncoderBuilder},
+    decoder::{CatchResult, Decoder},
+    droplet::DropType,
+    encoder::{Encoder, EncoderType},
+};
+
+fn msg() -> Vec<u8> {
+    (0..1_000).map(|i| (i * 7) as u8).collect()
+}
+
+// The source block of every droplet, `None` for repair droplets.
+fn blocks(enc: Encoder, cnt: usize) -> Vec<Option<usize>> {
+    enc.take(cnt)
+        .map(|drop| match drop.droptype {
+            DropType::Edges(idx) => Some(idx),
+            DropType::Seeded(..) => None,
+        })
+        .collect()
+}
+
+#[test]
+fn systematic_rounds() {
+    for rounds in 0..4 {
+        let enc =
+            Encoder::ideal(msg(), 100, EncoderType::Systematic).with_systematic_rounds(rounds);
+        let expected: Vec<Option<usize>> = (0..50)
+            .map(|n| if n < rounds * 10 { Some(n % 10) } else { None })
+            .collect();
+        assert_eq!(blocks(enc, 50), expected);
+    }
+    let enc = Encoder::ideal(msg(), 100, EncoderType::Systematic);
+    assert_eq!(blocks(enc, 21).iter().filter(|b| b.is_some()).count(), 20);
+}
+
+#[test]
+fn interleaved_repair() {
+    let enc = Encoder::ideal(msg(), 100, EncoderType::Systematic)
+        .with_systematic_rounds(1)
+        .with_repair_interval(3);
+    let expected = vec![
+        Some(0),
+        Some(1),
+        Some(2),
+        None,
+        Some(3),
+        Some(4),
+        Some(5),
+        None,
+        Some(6),
+        Some(7),
+        Some(8),
+        None,
+        Some(9),
+        None,
+        None,
+    ];
+    assert_eq!(blocks(enc.clone(), expected.len()), expected);
+
+    let drops: Vec<_> = enc.clone().take(20).collect();
+    for (n, drop) in drops.iter().enumerate() {
+        assert_eq!(&enc.drop_at(n), drop);
+    }
+}
+
+#[test]
+fn one_round_decodes_without_loss() {
+    let mut enc = Encoder::ideal(msg(), 100, EncoderType::Systematic).with_systematic_rounds(1);
+    let mut dec = Decoder::new(msg().len(), 100);
+    for n in 1..=10 {
+        match dec.catch(enc.drop()) {
+            CatchResult::Missing(stats) => assert_eq!(stats.unknown_chunks, 10 - n),
+            CatchResult::Finished(data, stats) => {
+                assert_eq!(n, 10);
+                assert_eq!(data, &msg()[..]);
+                assert_eq!(stats.cnt_redundant, 0);
+            }
+        }
+    }
+    assert!(dec.is_finished());
+}
+
+#[test]
+fn systematic_droplets_fill_pending_blocks() {
+    // Repair droplets first, then the source blocks, which have to
+    // reduce the repair droplets waiting for them
+    let msg = msg();
+    let random = Encoder::ideal(msg.clone(), 100, EncoderType::Random).with_seed(3);
+    let mut systematic = Encoder::ideal(msg.clone(), 100, EncoderType::Systematic);
+    let mut dec = Decoder::new(msg.len(), 100);
+    for drop in random.take(5) {
+        dec.catch(drop);
+    }
+    let data = loop {
+        if let CatchResult::Finished(data, _) = dec.catch(systematic.drop()) {
+            break data.to_vec();
+        }
+    };
+    assert_eq!(data, msg);
+    assert!(systematic.cnt <= 10);
+}
+
+#[test]
+fn builder_repair_interval() {
+    let enc = EncoderBuilder::new(msg())
+        .blocksize(100)
+        .encoder_type(EncoderType::Systematic)
+        .systematic_rounds(1)
+        .repair_interval(4)
+        .build()
+        .unwrap();
+    assert_eq!(blocks(enc, 5)[4], None);
+
+    assert_eq!(
+        EncoderBuilder::new(msg()).repair_interval(0).build().err(),
+        Some(ConfigError::ZeroRepairInterval)
+    );
+}
@@ -0,0 +1,65 @@
+use fountaincode::xor::{xor_bytes, xor_many, Kernel};
+use proptest::prelude::*;
+
+const KERNELS: [Kernel; 4] = [Kernel::Avx2, Kernel::Sse2, Kernel::Neon, Kernel::Words];
+
+fn xor_scalar(lhs: &mut [u8], rhs: &[u8]) {
+    for (l, r) in lhs.iter_mut().zip(rhs) {
+        *l ^= *r;
+    }
+}
+
+#[test]
+fn detected_kernel_is_supported() {
+    let kernel = Kernel::detect();
+    assert!(kernel.is_supported());
+    assert_eq!(Kernel::detect(), kernel);
+    assert!(Kernel::Words.is_supported());
+}
+
+proptest! {
+    #[test]
+    fn kernels_match_scalar(
+        lhs in prop::collection::vec(any::<u8>(), 0..300),
+        rhs in prop::collection::vec(any::<u8>(), 0..300),
+        offset in 0_usize..8,
+    ) {
+        // Unaligned slices and different lengths
+        let offset = offset.min(lhs.len()).min(rhs.len());
+        let mut expected = lhs.clone();
+        xor_scalar(&mut expected[offset..], &rhs[offset..]);
+
+        for kernel in KERNELS.iter().filter(|k| k.is_supported()) {
+            let mut result = lhs.clone();
+            kernel.xor(&mut result[offset..], &rhs[offset..]);
+            prop_assert_eq!(&result, &expected, "{:?}", kernel);
+        }
+        let mut result = lhs;
+        xor_bytes(&mut result[offset..], &rhs[offset..]);
+        prop_assert_eq!(result, expected);
+    }
+}
+
+proptest! {
+    #[test]
+    fn xor_many_matches_xor_bytes(
+        dst in prop::collection::vec(any::<u8>(), 0..300),
+        srcs in prop::collection::vec(prop::collection::vec(any::<u8>(), 0..300), 0..10),
+    ) {
+        // Sources longer and shorter than the destination
+        let mut expected = dst.clone();
+        for src in &srcs {
+            xor_bytes(&mut expected, src);
+        }
+        let srcs: Vec<&[u8]> = srcs.iter().map(|s| &s[..]).collect();
+
+        for kernel in KERNELS.iter().filter(|k| k.is_supported()) {
+            let mut result = dst.clone();
+            kernel.xor_many(&mut result, &srcs);
+            prop_assert_eq!(&result, &expected, "{:?}", kernel);
+        }
+        let mut result = dst;
+        xor_many(&mut result, &srcs);
+        prop_assert_eq!(result, expected);
+    }
+}